@@ -0,0 +1,63 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::env;
+use std::fs;
+use std::path::Path;
+
+//folders and files packed into the executable by the embed-assets feature
+const EMBEDDED: &[&str] = &["assets.txt", "presets.txt", "animations.txt", "textures", "audio", "lang"];
+
+fn collect(root: &Path, relative: &str, files: &mut Vec<String>) {
+    let path = root.join(relative);
+    if (path.is_file()) {
+        files.push(relative.to_string());
+        return;
+    }
+    if let Ok(entries) = fs::read_dir(&path) {
+        for entry in entries.filter_map(|el| el.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            collect(root, &format!("{}/{}", relative, name), files);
+        }
+    }
+}
+
+fn main() {
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let root = Path::new(&root);
+    for path in EMBEDDED {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+    let mut files = Vec::new();
+    if (env::var("CARGO_FEATURE_EMBED_ASSETS").is_ok()) {
+        for path in EMBEDDED {
+            collect(root, path, &mut files);
+        }
+        files.sort();
+    }
+    let mut code = String::from("pub static FILES: &[(&str, &[u8])] = &[\n");
+    for file in &files {
+        let absolute = root.join(file).to_string_lossy().into_owned();
+        code.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            file, absolute
+        ));
+    }
+    code.push_str("];\n");
+    let out = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out).join("embedded.rs"), code).unwrap();
+}
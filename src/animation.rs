@@ -0,0 +1,253 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::clock::GameClock;
+use raylib::prelude::Rectangle;
+use std::collections::HashMap;
+
+pub const ANIMATIONS: &str = "animations.txt";
+
+#[derive(Clone)]
+pub struct Frame {
+    pub source: Rectangle,
+    //0 holds the frame until another clip is played
+    pub duration: u64,
+    //raised when the frame comes up
+    pub event: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    //a clip played once stops on its last frame
+    pub looping: bool,
+}
+
+//the clips of every texture, by texture id and then by clip name
+pub type Clips = HashMap<String, HashMap<String, Clip>>;
+
+//"texture clip loop|once frame..." per line, # starts a comment
+pub fn parse_clips(text: &str) -> (Clips, Vec<(usize, String)>) {
+    let mut clips: Clips = HashMap::new();
+    let mut errors = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [texture, name, looping, frames @ ..] => match parse_clip(looping, frames) {
+                Ok(clip) => {
+                    clips
+                        .entry(texture.to_string())
+                        .or_default()
+                        .insert(name.to_string(), clip);
+                }
+                Err(reason) => errors.push((number + 1, reason)),
+            },
+            _ => errors.push((number + 1, format!("cannot understand \"{}\"", line))),
+        }
+    }
+    (clips, errors)
+}
+
+fn parse_clip(looping: &str, words: &[&str]) -> Result<Clip, String> {
+    let looping = match looping {
+        "loop" => true,
+        "once" => false,
+        _ => return Err(format!("{} is neither loop nor once", looping)),
+    };
+    let mut frames: Vec<Frame> = Vec::new();
+    for word in words {
+        let number = |el: &str| {
+            el.parse::<u32>()
+                .map_err(|_| format!("{} is not a number", el))
+        };
+        if let Some(event) = word.strip_prefix('@') {
+            let last = frames
+                .last_mut()
+                .ok_or("an event needs a frame before it")?;
+            last.event = Some(event.to_string());
+        } else if let Some((step, count)) = word.split_once('=') {
+            //right=N and down=N repeat the last frame moving it by its own size each time
+            let last = frames
+                .last()
+                .ok_or("a repeat needs a frame before it")?
+                .clone();
+            let (step_x, step_y) = match step {
+                "right" => (last.source.width, 0.0),
+                "down" => (0.0, last.source.height),
+                _ => return Err(format!("unknown repeat {}", step)),
+            };
+            for index in 1..number(count)? {
+                let mut frame = last.clone();
+                frame.source.x += step_x * index as f32;
+                frame.source.y += step_y * index as f32;
+                frame.event = None;
+                frames.push(frame);
+            }
+        } else {
+            let values = word
+                .split(',')
+                .map(number)
+                .collect::<Result<Vec<u32>, String>>()?;
+            match values.as_slice() {
+                [x, y, width, height, duration] => frames.push(Frame {
+                    source: Rectangle {
+                        x: *x as f32,
+                        y: *y as f32,
+                        width: *width as f32,
+                        height: *height as f32,
+                    },
+                    duration: *duration as u64,
+                    event: None,
+                }),
+                _ => return Err(format!("a frame is x,y,w,h,ms not {}", word)),
+            }
+        }
+    }
+    if (frames.is_empty()) {
+        return Err("a clip needs at least one frame".to_string());
+    }
+    Ok(Clip { frames, looping })
+}
+
+//plays the clips of a sprite by name, an entity keeps one and tells it which clip it wants
+#[derive(Clone)]
+pub struct Animator {
+    clip: String,
+    frame: usize,
+    frame_start: u64,
+    started: bool,
+    finished: bool,
+}
+
+impl Animator {
+    pub fn new(clip: &str) -> Self {
+        Self {
+            clip: clip.to_string(),
+            frame: 0,
+            frame_start: 0,
+            started: false,
+            finished: false,
+        }
+    }
+    //asking for the clip already playing keeps it going
+    pub fn play(&mut self, clip: &str) {
+        if (self.clip != clip) {
+            self.restart(clip);
+        }
+    }
+    pub fn restart(&mut self, clip: &str) {
+        *self = Animator::new(clip);
+    }
+    //moves to the next frame when the current one has been shown long enough,
+    //returns the event of the frame that just came up
+    pub fn update(&mut self, clock: &GameClock, clips: &HashMap<String, Clip>) -> Option<String> {
+        let clip = clips.get(&self.clip)?;
+        if (!self.started) {
+            self.started = true;
+            self.frame_start = clock.now();
+            return clip.frames[0].event.clone();
+        }
+        let frame = &clip.frames[self.frame.min(clip.frames.len() - 1)];
+        if (self.finished
+            || frame.duration == 0
+            || clock.elapsed_since(self.frame_start) < frame.duration)
+        {
+            return None;
+        }
+        self.frame_start = clock.now();
+        if (self.frame + 1 < clip.frames.len()) {
+            self.frame += 1;
+        } else if (clip.looping) {
+            self.frame = 0;
+        } else {
+            self.finished = true;
+            return None;
+        }
+        clip.frames[self.frame].event.clone()
+    }
+    //the part of the texture to draw, None when the sprite has no such clip
+    pub fn source(&self, clips: &HashMap<String, Clip>) -> Option<Rectangle> {
+        let frames = &clips.get(&self.clip)?.frames;
+        Some(frames[self.frame.min(frames.len() - 1)].source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clips() -> HashMap<String, Clip> {
+        let (mut clips, errors) = parse_clips(
+            "mouse walk loop 0,0,8,8,100 @step right=3\nmouse die once 0,8,8,8,50 right=2",
+        );
+        assert!(errors.is_empty());
+        clips.remove("mouse").unwrap()
+    }
+
+    #[test]
+    fn frames_follow_the_game_clock() {
+        let clips = clips();
+        let mut clock = GameClock::manual();
+        let mut animator = Animator::new("walk");
+        let x = |animator: &Animator| animator.source(&clips).unwrap().x;
+        assert_eq!(animator.update(&clock, &clips), Some("step".to_string()));
+        clock.advance(99);
+        assert_eq!(animator.update(&clock, &clips), None);
+        assert_eq!(x(&animator), 0.0);
+        clock.advance(1);
+        animator.update(&clock, &clips);
+        assert_eq!(x(&animator), 8.0);
+        //a paused clock holds the frame however long the game waits
+        clock.pause();
+        clock.advance(1000);
+        animator.update(&clock, &clips);
+        assert_eq!(x(&animator), 8.0);
+        //at half speed every frame lasts twice as long
+        clock.resume();
+        clock.set_time_scale(0.5);
+        clock.advance(150);
+        animator.update(&clock, &clips);
+        assert_eq!(x(&animator), 8.0);
+        clock.advance(50);
+        animator.update(&clock, &clips);
+        assert_eq!(x(&animator), 16.0);
+        clock.advance(200);
+        assert_eq!(animator.update(&clock, &clips), Some("step".to_string()));
+        assert_eq!(x(&animator), 0.0);
+    }
+
+    #[test]
+    fn clips_played_once_stop_on_their_last_frame() {
+        let clips = clips();
+        let mut clock = GameClock::manual();
+        let mut animator = Animator::new("die");
+        for _ in 0..4 {
+            animator.update(&clock, &clips);
+            clock.advance(50);
+        }
+        assert_eq!(animator.source(&clips).unwrap().x, 8.0);
+        //the last frame stays on screen however long the clip keeps being updated
+        clock.advance(500);
+        assert!(animator.update(&clock, &clips).is_none());
+        assert_eq!(animator.source(&clips).unwrap().x, 8.0);
+        assert!(Animator::new("fly").update(&clock, &clips).is_none());
+    }
+}
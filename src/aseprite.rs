@@ -0,0 +1,323 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::animation::{Clip, Frame};
+use crate::json::{self, Value};
+use raylib::prelude::Rectangle;
+use std::collections::HashMap;
+
+//a tag played more times than this is not something an export does, each repeat copies its frames
+const MAX_REPEAT: usize = 64;
+
+//the clips of a sheet exported from aseprite with json data, as an array or a hash,
+//one clip per tag or a single looping "idle" clip when the sheet has no tags
+pub fn import(text: &str) -> Result<HashMap<String, Clip>, String> {
+    let document = json::parse(text)?;
+    let frames: Vec<&Value> = match document.get("frames") {
+        Some(Value::Array(frames)) => frames.iter().collect(),
+        //the hash export keys the frames by file name, in order
+        Some(Value::Object(frames)) => frames.iter().map(|el| &el.1).collect(),
+        _ => return Err("there are no frames".to_string()),
+    };
+    let frames = frames
+        .into_iter()
+        .map(frame)
+        .collect::<Result<Vec<Frame>, String>>()?;
+    let tags = document
+        .get("meta")
+        .and_then(|el| el.get("frameTags"))
+        .and_then(Value::as_array)
+        .unwrap_or(&[]);
+    let mut clips = HashMap::new();
+    if (tags.is_empty()) {
+        clips.insert("idle".to_string(), Clip { frames, looping: true });
+        return Ok(clips);
+    }
+    for tag in tags {
+        let (name, clip) = tag_clip(tag, &frames)?;
+        clips.insert(name, clip);
+    }
+    Ok(clips)
+}
+
+fn number(value: &Value, key: &str) -> Result<f64, String> {
+    value
+        .get(key)
+        .and_then(Value::as_f64)
+        .ok_or(format!("{} is missing", key))
+}
+
+fn frame(value: &Value) -> Result<Frame, String> {
+    let flag = |key: &str| value.get(key).and_then(Value::as_bool) == Some(true);
+    //the sprites are drawn from the corner of their frame, a trimmed frame would be drawn out of place
+    if (flag("trimmed") || flag("rotated")) {
+        return Err("export the sheet without trimming or rotating the frames".to_string());
+    }
+    let source = value.get("frame").ok_or("a frame has no rectangle")?;
+    Ok(Frame {
+        source: Rectangle {
+            x: number(source, "x")? as f32,
+            y: number(source, "y")? as f32,
+            width: number(source, "w")? as f32,
+            height: number(source, "h")? as f32,
+        },
+        duration: number(value, "duration")? as u64,
+        event: None,
+    })
+}
+
+fn tag_clip(tag: &Value, frames: &[Frame]) -> Result<(String, Clip), String> {
+    let name = tag
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or("a tag has no name")?;
+    let (from, to) = (number(tag, "from")? as usize, number(tag, "to")? as usize);
+    if (from > to || to >= frames.len()) {
+        return Err(format!("the tag {} is outside the sheet", name));
+    }
+    let forward = &frames[from..=to];
+    //a ping-pong does not show the end frames twice when it turns around
+    let inner = forward.len().saturating_sub(2);
+    let order: Vec<Frame> = match tag
+        .get("direction")
+        .and_then(Value::as_str)
+        .unwrap_or("forward")
+    {
+        "forward" => forward.to_vec(),
+        "reverse" => forward.iter().rev().cloned().collect(),
+        "pingpong" => forward
+            .iter()
+            .chain(forward.iter().rev().skip(1).take(inner))
+            .cloned()
+            .collect(),
+        "pingpong_reverse" => forward
+            .iter()
+            .rev()
+            .chain(forward.iter().skip(1).take(inner))
+            .cloned()
+            .collect(),
+        other => {
+            return Err(format!(
+                "the tag {} has an unknown direction {}",
+                name, other
+            ))
+        }
+    };
+    //newer versions export how many times a tag plays, as text, no count or 0 means forever
+    let repeat = tag
+        .get("repeat")
+        .and_then(|el| {
+            el.as_str()
+                .and_then(|el| el.parse::<f64>().ok())
+                .or(el.as_f64())
+        })
+        .unwrap_or(0.0);
+    if (repeat > MAX_REPEAT as f64) {
+        return Err(format!(
+            "the tag {} repeats {} times, at most {} are allowed",
+            name, repeat, MAX_REPEAT
+        ));
+    }
+    let repeat = repeat as usize;
+    let frames = (0..repeat.max(1))
+        .flat_map(|_| order.iter().cloned())
+        .collect();
+    Ok((name.to_string(), Clip { frames, looping: repeat == 0 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //four 9x9 frames side by side, as File > Export Sprite Sheet writes them with json data as an array
+    const ARRAY_EXPORT: &str = r##"{ "frames": [
+   {
+    "filename": "mouse 0.aseprite",
+    "frame": { "x": 0, "y": 0, "w": 9, "h": 9 },
+    "rotated": false,
+    "trimmed": false,
+    "spriteSourceSize": { "x": 0, "y": 0, "w": 9, "h": 9 },
+    "sourceSize": { "w": 9, "h": 9 },
+    "duration": 100
+   },
+   {
+    "filename": "mouse 1.aseprite",
+    "frame": { "x": 9, "y": 0, "w": 9, "h": 9 },
+    "rotated": false,
+    "trimmed": false,
+    "spriteSourceSize": { "x": 0, "y": 0, "w": 9, "h": 9 },
+    "sourceSize": { "w": 9, "h": 9 },
+    "duration": 120
+   },
+   {
+    "filename": "mouse 2.aseprite",
+    "frame": { "x": 18, "y": 0, "w": 9, "h": 9 },
+    "rotated": false,
+    "trimmed": false,
+    "spriteSourceSize": { "x": 0, "y": 0, "w": 9, "h": 9 },
+    "sourceSize": { "w": 9, "h": 9 },
+    "duration": 140
+   },
+   {
+    "filename": "mouse 3.aseprite",
+    "frame": { "x": 27, "y": 0, "w": 9, "h": 9 },
+    "rotated": false,
+    "trimmed": false,
+    "spriteSourceSize": { "x": 0, "y": 0, "w": 9, "h": 9 },
+    "sourceSize": { "w": 9, "h": 9 },
+    "duration": 160
+   }
+ ],
+ "meta": {
+  "app": "https://www.aseprite.org/",
+  "version": "1.3.2-x64",
+  "image": "mouse.png",
+  "format": "RGBA8888",
+  "size": { "w": 36, "h": 9 },
+  "scale": "1",
+  "frameTags": [
+   { "name": "walk", "from": 0, "to": 3, "direction": "pingpong", "color": "#000000ff" },
+   { "name": "back", "from": 1, "to": 3, "direction": "pingpong_reverse", "color": "#000000ff" },
+   { "name": "die", "from": 2, "to": 3, "direction": "reverse", "color": "#000000ff", "repeat": "2" }
+  ],
+  "layers": [
+   { "name": "Layer 1", "opacity": 255, "blendMode": "normal" }
+  ],
+  "slices": [
+  ]
+ }
+}
+"##;
+
+    //the same kind of sheet exported as a hash, older versions write repeat as a number
+    const HASH_EXPORT: &str = r##"{ "frames": {
+   "cheese 0.aseprite": {
+    "frame": { "x": 0, "y": 0, "w": 5, "h": 5 },
+    "rotated": false,
+    "trimmed": false,
+    "spriteSourceSize": { "x": 0, "y": 0, "w": 5, "h": 5 },
+    "sourceSize": { "w": 5, "h": 5 },
+    "duration": 80
+   },
+   "cheese 1.aseprite": {
+    "frame": { "x": 5, "y": 0, "w": 5, "h": 5 },
+    "rotated": false,
+    "trimmed": false,
+    "spriteSourceSize": { "x": 0, "y": 0, "w": 5, "h": 5 },
+    "sourceSize": { "w": 5, "h": 5 },
+    "duration": 90
+   }
+ },
+ "meta": {
+  "app": "https://www.aseprite.org/",
+  "version": "1.2.40-x64",
+  "image": "cheese.png",
+  "format": "RGBA8888",
+  "size": { "w": 10, "h": 5 },
+  "scale": "1",
+  "frameTags": [
+   { "name": "shine", "from": 0, "to": 1, "direction": "forward", "repeat": 3 }
+  ],
+  "slices": [
+  ]
+ }
+}
+"##;
+
+    //x and duration of every frame of a clip
+    fn frames(clips: &HashMap<String, Clip>, name: &str) -> Vec<(f32, u64)> {
+        clips[name]
+            .frames
+            .iter()
+            .map(|el| (el.source.x, el.duration))
+            .collect()
+    }
+
+    #[test]
+    fn array_exports_become_one_clip_per_tag() {
+        let clips = import(ARRAY_EXPORT).unwrap();
+        assert_eq!(clips.len(), 3);
+        assert_eq!(
+            frames(&clips, "walk"),
+            vec![
+                (0.0, 100),
+                (9.0, 120),
+                (18.0, 140),
+                (27.0, 160),
+                (18.0, 140),
+                (9.0, 120)
+            ]
+        );
+        assert!(clips["walk"].looping);
+        assert_eq!(
+            frames(&clips, "back"),
+            vec![(27.0, 160), (18.0, 140), (9.0, 120), (18.0, 140)]
+        );
+        assert_eq!(
+            frames(&clips, "die"),
+            vec![(27.0, 160), (18.0, 140), (27.0, 160), (18.0, 140)]
+        );
+        assert!(!clips["die"].looping);
+        assert_eq!(clips["walk"].frames[0].source.width, 9.0);
+    }
+
+    #[test]
+    fn hash_exports_keep_the_frame_order() {
+        let clips = import(HASH_EXPORT).unwrap();
+        assert_eq!(
+            frames(&clips, "shine"),
+            vec![
+                (0.0, 80),
+                (5.0, 90),
+                (0.0, 80),
+                (5.0, 90),
+                (0.0, 80),
+                (5.0, 90)
+            ]
+        );
+        assert!(!clips["shine"].looping);
+        let untagged = HASH_EXPORT.replace("frameTags", "tags");
+        let clips = import(&untagged).unwrap();
+        assert_eq!(frames(&clips, "idle"), vec![(0.0, 80), (5.0, 90)]);
+        assert!(clips["idle"].looping);
+    }
+
+    #[test]
+    fn broken_sheets_are_refused() {
+        let broken = [
+            ARRAY_EXPORT.replacen(r#""trimmed": false"#, r#""trimmed": true"#, 1),
+            ARRAY_EXPORT.replacen(r#""rotated": false"#, r#""rotated": true"#, 1),
+            ARRAY_EXPORT.replace(
+                r#""to": 3, "direction": "pingpong""#,
+                r#""to": 4, "direction": "pingpong""#,
+            ),
+            ARRAY_EXPORT.replace(r#""from": 2, "to": 3"#, r#""from": 3, "to": 2"#),
+            ARRAY_EXPORT.replace(r#""direction": "reverse""#, r#""direction": "sideways""#),
+            ARRAY_EXPORT.replace(r#""name": "walk", "#, ""),
+            ARRAY_EXPORT.replacen(r#""duration": 100"#, r#""duration": "100""#, 1),
+            ARRAY_EXPORT.replace(r#""repeat": "2""#, r#""repeat": "1e9""#),
+            ARRAY_EXPORT.replace(r#""repeat": "2""#, r#""repeat": 65"#),
+            ARRAY_EXPORT.replace(r#""frames": ["#, r#""sprites": ["#),
+            ARRAY_EXPORT[..ARRAY_EXPORT.len() / 2].to_string(),
+            String::from("{\"frames\": [1-2]}"),
+        ];
+        for text in &broken {
+            assert!(import(text).is_err());
+        }
+        assert!(import(&ARRAY_EXPORT.replace(r#""repeat": "2""#, r#""repeat": "64""#)).is_ok());
+    }
+}
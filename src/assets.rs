@@ -0,0 +1,840 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::animation::{self, Clip, Clips, ANIMATIONS};
+use crate::aseprite;
+use crate::audio::Audio;
+use crate::clock::GameClock;
+use crate::embedded;
+use crate::synth::{self, Preset, PRESETS};
+use raylib::prelude::*;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::marker::PhantomData;
+use std::ops::Index;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const MANIFEST: &str = "assets.txt";
+//how often the files are checked for changes when hot reload is on
+const RELOAD_INTERVAL: u64 = 500;
+//every sound is loaded once per pan position, from all left to all right,
+//the raylib version we use cannot pan a sound while playing it
+const PAN_STEPS: usize = 5;
+
+//every id the code looks up, the manifest is checked against them at startup
+pub const TEXTURES: &[&str] = &["mouse", "cheese", "spider", "exit", "title", "extra_cheese"];
+pub const SOUNDS: &[&str] = &[
+    "click",
+    "menu_move",
+    "pickup",
+    "death",
+    "exit_open",
+    "level_start",
+    "power_up",
+    "spider_wake",
+    "spider_step",
+];
+pub const MUSIC: &[&str] = &["menu", "play", "play_tense"];
+
+pub enum AssetError {
+    Missing { path: String },
+    Invalid { path: String, reason: String },
+    Undeclared { id: String },
+}
+
+impl AssetError {
+    fn from_load(path: &str, reason: String) -> Self {
+        if (Path::new(path).exists()) {
+            AssetError::Invalid { path: path.to_string(), reason }
+        } else {
+            AssetError::Missing { path: path.to_string() }
+        }
+    }
+    pub fn path(&self) -> &str {
+        match self {
+            AssetError::Missing { path } => path,
+            AssetError::Invalid { path, .. } => path,
+            AssetError::Undeclared { id } => id,
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::Missing { path } => write!(f, "missing {}", path),
+            AssetError::Invalid { path, reason } => write!(f, "cannot load {}: {}", path, reason),
+            AssetError::Undeclared { id } => write!(f, "{} is not in {}", id, MANIFEST),
+        }
+    }
+}
+
+//a texture and the animation clips cut from it, by name
+pub struct Sprite {
+    pub texture: Texture2D,
+    pub clips: HashMap<String, Clip>,
+}
+
+//how a sound is played, every play picks a volume and a pitch within the variations
+#[derive(Copy, Clone)]
+pub struct SoundSettings {
+    pub volume: f32,
+    pub pitch: f32,
+    pub volume_vary: f32,
+    pub pitch_vary: f32,
+    //how many copies of the sound may be heard at the same time
+    pub max: u32,
+}
+
+impl SoundSettings {
+    pub fn new() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+            volume_vary: 0.0,
+            pitch_vary: 0.0,
+            max: 4,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct SoundInfo {
+    settings: SoundSettings,
+    //in milliseconds, 0 for a sound that was not loaded
+    length: u64,
+}
+
+//a typed index into the resource manager, cheap to copy and to keep around
+pub struct Handle<T> {
+    index: usize,
+    kind: PhantomData<T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: usize) -> Self {
+        Self { index, kind: PhantomData }
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle::new(self.index)
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+//the first entry of each table is the fallback every unknown id resolves to
+struct Table<T> {
+    ids: HashMap<String, usize>,
+    entries: Vec<T>,
+}
+
+impl<T> Table<T> {
+    fn new(fallback: T) -> Self {
+        Self { ids: HashMap::new(), entries: vec![fallback] }
+    }
+    fn insert(&mut self, id: &str, entry: T) -> usize {
+        match self.ids.get(id) {
+            Some(index) => {
+                self.entries[*index] = entry;
+                *index
+            }
+            None => {
+                self.ids.insert(id.to_string(), self.entries.len());
+                self.entries.push(entry);
+                self.entries.len() - 1
+            }
+        }
+    }
+    //another id for an entry that is already loaded
+    fn alias(&mut self, id: &str, index: usize) {
+        self.ids.insert(id.to_string(), index);
+    }
+    fn index(&self, id: &str) -> usize {
+        self.ids.get(id).copied().unwrap_or(0)
+    }
+    fn handle(&self, id: &str) -> Handle<T> {
+        Handle::new(self.index(id))
+    }
+}
+
+//what the game knows about its sounds without a window or a sound card,
+//the samples themselves are handed to the audio backend
+pub struct Sounds {
+    table: Table<SoundInfo>,
+    volume: f32,
+}
+
+impl Sounds {
+    pub fn new() -> Self {
+        Self {
+            table: Table::new(SoundInfo { settings: SoundSettings::new(), length: 0 }),
+            volume: 1.0,
+        }
+    }
+    pub fn insert(&mut self, id: &str, settings: SoundSettings, length: u64) -> Handle<Sound> {
+        Handle::new(self.table.insert(id, SoundInfo { settings, length }))
+    }
+    pub fn handle(&self, id: &str) -> Handle<Sound> {
+        Handle::new(self.table.index(id))
+    }
+    pub fn settings(&self, handle: Handle<Sound>) -> SoundSettings {
+        self.table.entries[handle.index].settings
+    }
+    pub fn length(&self, handle: Handle<Sound>) -> u64 {
+        self.table.entries[handle.index].length
+    }
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+    //applied on top of each sound's own volume when it is played
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+}
+
+enum Entry {
+    Texture {
+        id: String,
+        path: String,
+        sheet: Option<String>,
+    },
+    Sound {
+        id: String,
+        path: String,
+        settings: SoundSettings,
+    },
+    Synth {
+        id: String,
+        preset: String,
+        settings: SoundSettings,
+    },
+    Music {
+        id: String,
+        path: String,
+    },
+}
+
+fn sound_settings(options: &[&str]) -> Result<SoundSettings, String> {
+    let mut settings = SoundSettings::new();
+    for option in options {
+        let (name, value) = option
+            .split_once('=')
+            .ok_or("sound options are name=value")?;
+        let value = value
+            .parse::<f32>()
+            .map_err(|_| format!("{} is not a number", value))?;
+        match name {
+            "volume" => settings.volume = value,
+            "pitch" => settings.pitch = value,
+            "volume_vary" => settings.volume_vary = value,
+            "pitch_vary" => settings.pitch_vary = value,
+            "max" => settings.max = value.max(1.0) as u32,
+            _ => return Err(format!("unknown sound option {}", name)),
+        }
+    }
+    Ok(settings)
+}
+
+//"texture id path [aseprite json]", "sound id path [name=value...]",
+//"synth id preset [name=value...]" or "music id path", # starts a comment
+fn parse_line(line: &str) -> Result<Entry, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["texture", id, path, sheet @ ..] if sheet.len() < 2 => Ok(Entry::Texture {
+            id: id.to_string(),
+            path: path.to_string(),
+            sheet: sheet.first().map(|el| el.to_string()),
+        }),
+        ["music", id, path] => Ok(Entry::Music { id: id.to_string(), path: path.to_string() }),
+        ["sound", id, path, options @ ..] => Ok(Entry::Sound {
+            id: id.to_string(),
+            path: path.to_string(),
+            settings: sound_settings(options)?,
+        }),
+        ["synth", id, preset, options @ ..] => Ok(Entry::Synth {
+            id: id.to_string(),
+            preset: preset.to_string(),
+            settings: sound_settings(options)?,
+        }),
+        _ => Err(format!("cannot understand \"{}\"", line)),
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Slot {
+    Texture(usize),
+    Sound(usize),
+    Music(usize),
+    Presets,
+    Animations,
+}
+
+//a loaded file and the modification time it had, None if it could not be read
+struct Watched {
+    slot: Slot,
+    path: String,
+    modified: Option<SystemTime>,
+    //the version that last failed to load, it is not read again until it changes
+    failed: Option<SystemTime>,
+}
+
+//raylib 3.7 only has the raw binding, a wave it cannot decode comes back empty
+fn load_wave_from_memory(file_type: &str, bytes: &[u8]) -> Result<Wave, String> {
+    let c_file_type = CString::new(file_type).map_err(|el| el.to_string())?;
+    let wave = unsafe {
+        ffi::LoadWaveFromMemory(c_file_type.as_ptr(), bytes.as_ptr(), bytes.len() as i32)
+    };
+    if (wave.data.is_null() || wave.sampleCount == 0) {
+        unsafe { ffi::UnloadWave(wave) };
+        return Err(format!("cannot decode the {} data", file_type));
+    }
+    Ok(unsafe { Wave::from_raw(wave) })
+}
+
+//an embedded track copied to the temp folder for raylib to stream from, deleted when dropped
+struct TempCopy(PathBuf);
+
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+//the ".png" style file type raylib wants when loading from memory
+fn extension(path: &str) -> String {
+    match Path::new(path).extension() {
+        Some(extension) => format!(".{}", extension.to_string_lossy()),
+        None => String::new(),
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|el| el.modified()).ok()
+}
+
+pub struct ResourceManager {
+    textures: Table<Sprite>,
+    //a sound or a track that cannot be loaded is just silence,
+    //the loaded ones belong to the audio backend and are played through their handles
+    sounds: Sounds,
+    music: Table<()>,
+    presets: HashMap<String, Preset>,
+    //sound index and preset name of every synthesized sound, rendered again when the presets change
+    synths: Vec<(usize, String)>,
+    audio_enabled: bool,
+    //texture id and path of the aseprite sheets, their clips come before the ones in animations.txt
+    sheets: Vec<(String, String)>,
+    errors: Vec<AssetError>,
+    watched: Vec<Watched>,
+    last_poll: u64,
+    temp_copies: Vec<TempCopy>,
+}
+
+impl ResourceManager {
+    fn empty(fallback: Texture2D, audio_enabled: bool) -> Self {
+        Self {
+            textures: Table::new(Sprite { texture: fallback, clips: HashMap::new() }),
+            sounds: Sounds::new(),
+            music: Table::new(()),
+            presets: HashMap::new(),
+            synths: Vec::new(),
+            audio_enabled,
+            sheets: Vec::new(),
+            errors: Vec::new(),
+            watched: Vec::new(),
+            last_poll: 0,
+            temp_copies: Vec::new(),
+        }
+    }
+    //nothing loaded and no window needed, every id resolves to a fallback
+    //whose texture id is 0, which raylib never unloads
+    #[cfg(test)]
+    pub fn headless() -> Self {
+        let texture = ffi::Texture { id: 0, width: 1, height: 1, mipmaps: 1, format: 0 };
+        Self::empty(unsafe { Texture2D::from_raw(texture) }, false)
+    }
+    //never fails, whatever cannot be loaded is replaced and reported by errors()
+    pub fn new(handle: &mut RaylibHandle, thread: &RaylibThread, audio: &mut dyn Audio) -> Self {
+        let mut errors = Vec::new();
+        let audio_enabled = audio.is_enabled();
+        let mut this = Self::empty(Self::default_texture(handle, thread), audio_enabled);
+        let manifest = match embedded::read_to_string(MANIFEST) {
+            Ok(manifest) => manifest,
+            Err(error) => {
+                errors.push(AssetError::from_load(MANIFEST, error.to_string()));
+                String::new()
+            }
+        };
+        let (presets, preset_errors) = Self::load_presets();
+        this.presets = presets;
+        errors.extend(preset_errors);
+        this.watch(Slot::Presets, PRESETS.to_string());
+        let mut music_paths = HashMap::new();
+        for (number, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if (line.is_empty() || line.starts_with('#')) {
+                continue;
+            }
+            match parse_line(line) {
+                Ok(Entry::Texture { id, path, sheet }) => {
+                    let texture = match Self::load_texture(handle, thread, &path) {
+                        Ok(texture) => texture,
+                        Err(error) => {
+                            errors.push(error);
+                            Self::default_texture(handle, thread)
+                        }
+                    };
+                    let index = this
+                        .textures
+                        .insert(&id, Sprite { texture, clips: HashMap::new() });
+                    this.watch(Slot::Texture(index), path);
+                    if let Some(sheet) = sheet {
+                        this.watch(Slot::Animations, sheet.clone());
+                        this.sheets.push((id, sheet));
+                    }
+                }
+                Ok(Entry::Sound { id, path, settings }) => {
+                    //without a device there is nothing to load the sounds into
+                    let (voices, length) = match audio_enabled.then(|| Self::load_audio(&path)) {
+                        Some(Ok(loaded)) => loaded,
+                        Some(Err(error)) => {
+                            errors.push(error);
+                            (Vec::new(), 0)
+                        }
+                        None => (Vec::new(), 0),
+                    };
+                    let sound = this.sounds.insert(&id, settings, length);
+                    audio.store_sound(sound, voices);
+                    this.watch(Slot::Sound(sound.index), path);
+                }
+                Ok(Entry::Synth { id, preset, settings }) => {
+                    let (voices, length) = match audio_enabled.then(|| this.load_synth(&preset)) {
+                        Some(Ok(loaded)) => loaded,
+                        Some(Err(error)) => {
+                            errors.push(error);
+                            (Vec::new(), 0)
+                        }
+                        None => (Vec::new(), 0),
+                    };
+                    let sound = this.sounds.insert(&id, settings, length);
+                    audio.store_sound(sound, voices);
+                    this.synths.push((sound.index, preset));
+                }
+                //two ids on the same file share one stream, so switching between them keeps playing
+                Ok(Entry::Music { id, path }) => match music_paths.get(&path) {
+                    Some(index) => this.music.alias(&id, *index),
+                    None => {
+                        let index = this.music.insert(&id, ());
+                        match audio_enabled.then(|| Self::load_music(thread, &path)) {
+                            Some(Ok((music, copy))) => {
+                                audio.store_music(Handle::new(index), music);
+                                this.temp_copies.extend(copy);
+                            }
+                            Some(Err(error)) => errors.push(error),
+                            None => (),
+                        }
+                        music_paths.insert(path.clone(), index);
+                        this.watch(Slot::Music(index), path);
+                    }
+                },
+                Err(reason) => errors.push(AssetError::Invalid {
+                    path: format!("{}:{}", MANIFEST, number + 1),
+                    reason,
+                }),
+            }
+        }
+        let (clips, animation_errors) = Self::load_animations(&this.sheets);
+        errors.extend(animation_errors);
+        errors.extend(this.set_clips(clips));
+        this.watch(Slot::Animations, ANIMATIONS.to_string());
+        for id in TEXTURES
+            .iter()
+            .filter(|el| !this.textures.ids.contains_key(**el))
+        {
+            errors.push(AssetError::Undeclared { id: id.to_string() });
+        }
+        for id in SOUNDS
+            .iter()
+            .filter(|el| !this.sounds.table.ids.contains_key(**el))
+        {
+            errors.push(AssetError::Undeclared { id: id.to_string() });
+        }
+        for id in MUSIC.iter().filter(|el| !this.music.ids.contains_key(**el)) {
+            errors.push(AssetError::Undeclared { id: id.to_string() });
+        }
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        this.errors = errors;
+        this
+    }
+    pub fn errors(&self) -> &[AssetError] {
+        &self.errors
+    }
+    fn watch(&mut self, slot: Slot, path: String) {
+        let modified = modified(&path);
+        self.watched
+            .push(Watched { slot, path, modified, failed: None });
+    }
+    //polls the files every RELOAD_INTERVAL and swaps in the ones that changed,
+    //a file that fails to load keeps the previous asset so a half saved png does no harm
+    pub fn reload_changed(
+        &mut self,
+        handle: &mut RaylibHandle,
+        thread: &RaylibThread,
+        audio: &mut dyn Audio,
+        clock: &GameClock,
+    ) {
+        if (clock.elapsed_since(self.last_poll) < RELOAD_INTERVAL) {
+            return;
+        }
+        self.last_poll = clock.now();
+        let mut presets_changed = false;
+        let mut animations = None;
+        for watched in &mut self.watched {
+            let modified = modified(&watched.path);
+            if (modified.is_none() || modified == watched.modified || modified == watched.failed) {
+                continue;
+            }
+            let is_audio = matches!(
+                watched.slot,
+                Slot::Sound(_) | Slot::Music(_) | Slot::Presets
+            );
+            if (!self.audio_enabled && is_audio) {
+                continue;
+            }
+            let result = match watched.slot {
+                Slot::Texture(index) => Self::load_texture(handle, thread, &watched.path)
+                    .map(|el| self.textures.entries[index].texture = el),
+                Slot::Sound(index) => Self::load_audio(&watched.path).map(|(voices, length)| {
+                    self.sounds.table.entries[index].length = length;
+                    audio.store_sound(Handle::new(index), voices);
+                }),
+                Slot::Music(index) => {
+                    Self::load_music(thread, &watched.path).map(|(music, copy)| {
+                        audio.store_music(Handle::new(index), music);
+                        self.temp_copies.extend(copy);
+                    })
+                }
+                //while the file has errors the sounds keep the previous presets
+                Slot::Presets => match Self::load_presets() {
+                    (presets, errors) if errors.is_empty() => {
+                        self.presets = presets;
+                        presets_changed = true;
+                        Ok(())
+                    }
+                    (_, mut errors) => Err(errors.remove(0)),
+                },
+                Slot::Animations => match Self::load_animations(&self.sheets) {
+                    (clips, errors) if errors.is_empty() => {
+                        animations = Some(clips);
+                        Ok(())
+                    }
+                    (_, mut errors) => Err(errors.remove(0)),
+                },
+            };
+            match result {
+                Ok(()) => {
+                    watched.modified = modified;
+                    watched.failed = None;
+                    println!("reloaded {}", watched.path);
+                }
+                Err(error) => {
+                    watched.failed = modified;
+                    eprintln!("{}, keeping the previous version", error);
+                }
+            }
+        }
+        if let Some(clips) = animations {
+            for error in self.set_clips(clips) {
+                eprintln!("{}", error);
+            }
+        }
+        if (presets_changed) {
+            for (index, preset) in &self.synths {
+                match self.load_synth(preset) {
+                    Ok((voices, length)) => {
+                        self.sounds.table.entries[*index].length = length;
+                        audio.store_sound(Handle::new(*index), voices);
+                    }
+                    Err(error) => eprintln!("{}, keeping the previous version", error),
+                }
+            }
+        }
+    }
+    pub fn texture(&self, id: &str) -> Handle<Sprite> {
+        self.textures.handle(id)
+    }
+    pub fn sound(&self, id: &str) -> Handle<Sound> {
+        self.sounds.handle(id)
+    }
+    pub fn sounds(&self) -> &Sounds {
+        &self.sounds
+    }
+    pub fn music(&self, id: &str) -> Handle<Music> {
+        Handle::new(self.music.index(id))
+    }
+    pub fn sound_volume(&self) -> f32 {
+        self.sounds.volume()
+    }
+    pub fn set_sound_volume(&mut self, volume: f32) {
+        self.sounds.set_volume(volume);
+    }
+
+    //a clip with an error is reported and left out, the others still load
+    //a sheet with an error is left out, a clip in animations.txt replaces a sheet clip with its name
+    fn load_animations(sheets: &[(String, String)]) -> (Clips, Vec<AssetError>) {
+        let mut clips: Clips = HashMap::new();
+        let mut errors = Vec::new();
+        for (id, path) in sheets {
+            let imported = embedded::read_to_string(path)
+                .map_err(|el| AssetError::from_load(path, el.to_string()))
+                .and_then(|el| {
+                    aseprite::import(&el)
+                        .map_err(|reason| AssetError::Invalid { path: path.to_string(), reason })
+                });
+            match imported {
+                Ok(imported) => clips.entry(id.to_string()).or_default().extend(imported),
+                Err(error) => errors.push(error),
+            }
+        }
+        let text = match embedded::read_to_string(ANIMATIONS) {
+            Ok(text) => text,
+            Err(error) => {
+                errors.push(AssetError::from_load(ANIMATIONS, error.to_string()));
+                return (clips, errors);
+            }
+        };
+        let (listed, listed_errors) = animation::parse_clips(&text);
+        for (id, listed) in listed {
+            clips.entry(id).or_default().extend(listed);
+        }
+        errors.extend(
+            listed_errors
+                .into_iter()
+                .map(|(number, reason)| AssetError::Invalid {
+                    path: format!("{}:{}", ANIMATIONS, number),
+                    reason,
+                }),
+        );
+        (clips, errors)
+    }
+    //hands every sprite its clips, the ids no texture has are returned
+    fn set_clips(&mut self, mut clips: Clips) -> Vec<AssetError> {
+        for (id, index) in &self.textures.ids {
+            self.textures.entries[*index].clips = clips.remove(id).unwrap_or_default();
+        }
+        clips
+            .into_keys()
+            .map(|id| AssetError::Undeclared { id })
+            .collect()
+    }
+    fn load_texture(
+        handle: &mut RaylibHandle,
+        thread: &RaylibThread,
+        path: &str,
+    ) -> Result<Texture2D, AssetError> {
+        if let Some(bytes) = Self::embedded(path) {
+            let invalid = |reason| AssetError::Invalid { path: path.to_string(), reason };
+            let image =
+                Image::load_image_from_mem(&extension(path), &bytes.to_vec(), bytes.len() as i32)
+                    .map_err(invalid)?;
+            return handle
+                .load_texture_from_image(thread, &image)
+                .map_err(invalid);
+        }
+        handle
+            .load_texture(thread, path)
+            .map_err(|el| AssetError::from_load(path, el))
+    }
+    //the embedded copy of path, unless a file on disk overrides it
+    fn embedded(path: &str) -> Option<&'static [u8]> {
+        if (embedded::is_overridden(path)) {
+            return None;
+        }
+        embedded::get(path)
+    }
+    //textures/default.png, or a magenta checkerboard if even that is gone
+    fn default_texture(handle: &mut RaylibHandle, thread: &RaylibThread) -> Texture2D {
+        if let Ok(texture) = Self::load_texture(handle, thread, "textures/default.png") {
+            return texture;
+        }
+        let image = Image::gen_image_checked(8, 8, 2, 2, Color::MAGENTA, Color::BLACK);
+        handle
+            .load_texture_from_image(thread, &image)
+            .expect("cannot create the fallback texture")
+    }
+
+    //the copies of the sound and its length in milliseconds
+    fn load_audio(path: &str) -> Result<(Vec<Sound>, u64), AssetError> {
+        let mut wave = match Self::embedded(path) {
+            Some(bytes) => load_wave_from_memory(&extension(path), bytes)
+                .map_err(|reason| AssetError::Invalid { path: path.to_string(), reason })?,
+            None => Wave::load_wave(path).map_err(|el| AssetError::from_load(path, el))?,
+        };
+        let (sample_rate, channels) = (wave.sampleRate, wave.channels);
+        wave.wave_format(sample_rate as i32, 16, channels as i32);
+        //once formatted the data is sampleCount 16 bit samples with the channels interleaved
+        let samples = unsafe {
+            std::slice::from_raw_parts(wave.data as *const i16, wave.sampleCount as usize)
+        };
+        let length = samples.len() as u64 * 1000 / (sample_rate as u64 * channels as u64).max(1);
+        Ok((
+            Self::voices(path, samples, sample_rate, channels as usize)?,
+            length,
+        ))
+    }
+    //one stereo copy per pan step, the side the sound is panned away from fades out
+    fn voices(
+        path: &str,
+        samples: &[i16],
+        sample_rate: u32,
+        channels: usize,
+    ) -> Result<Vec<Sound>, AssetError> {
+        let invalid = |reason| AssetError::Invalid { path: path.to_string(), reason };
+        let channels = channels.max(1);
+        let mut voices = Vec::with_capacity(PAN_STEPS);
+        for step in 0..PAN_STEPS {
+            let pan = step as f32 / (PAN_STEPS - 1) as f32 * 2.0 - 1.0;
+            let (left_gain, right_gain) = ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0));
+            let mut stereo = Vec::with_capacity(samples.len() / channels * 2);
+            for frame in samples.chunks_exact(channels) {
+                let (left, right) = (frame[0], frame[channels.min(2) - 1]);
+                stereo.push((left as f32 * left_gain) as i16);
+                stereo.push((right as f32 * right_gain) as i16);
+            }
+            let wave = load_wave_from_memory(".wav", &synth::to_wav(&stereo, sample_rate, 2))
+                .map_err(invalid)?;
+            voices.push(Sound::load_sound_from_wave(&wave).map_err(invalid)?);
+        }
+        Ok(voices)
+    }
+    //a preset with an error is reported and left out, the others still load
+    fn load_presets() -> (HashMap<String, Preset>, Vec<AssetError>) {
+        let text = match embedded::read_to_string(PRESETS) {
+            Ok(text) => text,
+            Err(error) => {
+                return (
+                    HashMap::new(),
+                    vec![AssetError::from_load(PRESETS, error.to_string())],
+                )
+            }
+        };
+        let (presets, errors) = synth::parse_presets(&text);
+        let errors = errors
+            .into_iter()
+            .map(|(number, reason)| AssetError::Invalid {
+                path: format!("{}:{}", PRESETS, number),
+                reason,
+            })
+            .collect();
+        (presets, errors)
+    }
+    fn load_synth(&self, preset: &str) -> Result<(Vec<Sound>, u64), AssetError> {
+        let invalid = |reason| AssetError::Invalid { path: PRESETS.to_string(), reason };
+        let preset = self
+            .presets
+            .get(preset)
+            .ok_or_else(|| invalid(format!("there is no preset {}", preset)))?;
+        let samples = preset.render();
+        let length = samples.len() as u64 * 1000 / synth::SAMPLE_RATE as u64;
+        Ok((
+            Self::voices(PRESETS, &samples, synth::SAMPLE_RATE, 1)?,
+            length,
+        ))
+    }
+    //streams from disk, an embedded track is copied to the temp folder first,
+    //the process id keeps two running games from writing over each other's copy
+    fn load_music(
+        thread: &RaylibThread,
+        path: &str,
+    ) -> Result<(Music, Option<TempCopy>), AssetError> {
+        let copy = match Self::embedded(path) {
+            Some(bytes) => {
+                let copy = env::temp_dir().join(format!(
+                    "cheese_adventure_{}_{}",
+                    std::process::id(),
+                    path.replace('/', "_")
+                ));
+                fs::write(&copy, bytes).map_err(|el| AssetError::Invalid {
+                    path: path.to_string(),
+                    reason: el.to_string(),
+                })?;
+                Some(TempCopy(copy))
+            }
+            None => None,
+        };
+        let stream_path = match &copy {
+            Some(copy) => copy.0.to_string_lossy().into_owned(),
+            None => path.to_string(),
+        };
+        let mut music = Music::load_music_stream(thread, &stream_path)
+            .map_err(|el| AssetError::from_load(path, el))?;
+        music.looping = true;
+        Ok((music, copy))
+    }
+}
+
+impl Index<Handle<Sprite>> for ResourceManager {
+    type Output = Sprite;
+    fn index(&self, handle: Handle<Sprite>) -> &Sprite {
+        &self.textures.entries[handle.index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthesized_wavs_load_from_memory() {
+        let samples: Vec<i16> = (0..200).map(|el| (el * 100 - 10000) as i16).collect();
+        let wav = synth::to_wav(&samples, 22050, 2);
+        let wave = load_wave_from_memory(".wav", &wav).unwrap();
+        assert_eq!(wave.sampleCount as usize, samples.len());
+        assert_eq!(wave.sampleRate, 22050);
+        assert_eq!(wave.channels, 2);
+        let loaded = unsafe { std::slice::from_raw_parts(wave.data as *const i16, samples.len()) };
+        assert_eq!(loaded, &samples[..]);
+        //a cut header, no samples and an unknown format are errors and not empty sounds
+        assert!(load_wave_from_memory(".wav", &wav[..20]).is_err());
+        assert!(load_wave_from_memory(".wav", &synth::to_wav(&[], 22050, 2)).is_err());
+        assert!(load_wave_from_memory(".xyz", &wav).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::assets::Handle;
+use raylib::prelude::*;
+
+//everything the game asks of the sound card, so it can run without one,
+//sounds and tracks are handed over once loaded and played by their handles from then on
+pub trait Audio {
+    //false when nothing can be heard, the resource manager skips loading sounds then
+    fn is_enabled(&self) -> bool;
+    //the copies of a sound panned from all left to all right, replacing the ones it had
+    fn store_sound(&mut self, sound: Handle<Sound>, voices: Vec<Sound>);
+    fn store_music(&mut self, music: Handle<Music>, stream: Music);
+    //several copies of the same sound can play at once, each with its own volume, pitch and pan,
+    //pan goes from -1 on the left to 1 on the right
+    fn play_sound(&mut self, sound: Handle<Sound>, volume: f32, pitch: f32, pan: f32);
+    fn play_music(&mut self, music: Handle<Music>);
+    fn stop_music(&mut self, music: Handle<Music>);
+    fn is_music_playing(&self, music: Handle<Music>) -> bool;
+    fn set_music_volume(&mut self, music: Handle<Music>, volume: f32);
+    //1 is the normal speed, a stream played faster also sounds higher
+    fn set_music_pitch(&mut self, music: Handle<Music>, pitch: f32);
+    //streams have to be fed every frame
+    fn update_music(&mut self, music: Handle<Music>);
+}
+
+//opens the audio device, or falls back to silence when there is none
+pub fn init() -> Box<dyn Audio> {
+    let mut device = RaylibAudio::init_audio_device();
+    if (!device.is_audio_device_ready()) {
+        eprintln!("no audio device, the game will be silent");
+        return Box::new(NullAudio::new());
+    }
+    device.set_master_volume(1.0);
+    Box::new(RaylibBackend { sounds: Vec::new(), music: Vec::new(), device })
+}
+
+//the sounds and streams come before the device so they are unloaded before it closes
+pub struct RaylibBackend {
+    sounds: Vec<Vec<Sound>>,
+    music: Vec<Option<Music>>,
+    device: RaylibAudio,
+}
+
+impl RaylibBackend {
+    //the stream together with the device, both are borrowed mutably by every call on a stream
+    fn music_mut(&mut self, music: Handle<Music>) -> Option<(&mut RaylibAudio, &mut Music)> {
+        let stream = self
+            .music
+            .get_mut(music.index())
+            .and_then(|el| el.as_mut())?;
+        Some((&mut self.device, stream))
+    }
+}
+
+impl Audio for RaylibBackend {
+    fn is_enabled(&self) -> bool {
+        true
+    }
+    fn store_sound(&mut self, sound: Handle<Sound>, voices: Vec<Sound>) {
+        if (self.sounds.len() <= sound.index()) {
+            self.sounds.resize_with(sound.index() + 1, Vec::new);
+        }
+        self.sounds[sound.index()] = voices;
+    }
+    fn store_music(&mut self, music: Handle<Music>, stream: Music) {
+        if (self.music.len() <= music.index()) {
+            self.music.resize_with(music.index() + 1, || None);
+        }
+        self.music[music.index()] = Some(stream);
+    }
+    fn play_sound(&mut self, sound: Handle<Sound>, volume: f32, pitch: f32, pan: f32) {
+        let voices = match self.sounds.get_mut(sound.index()) {
+            Some(voices) if !voices.is_empty() => voices,
+            _ => return,
+        };
+        //the copy closest to pan, the copy played takes the volume and pitch the sound has right now
+        let step = ((pan.clamp(-1.0, 1.0) + 1.0) / 2.0 * (voices.len() - 1) as f32).round();
+        let sound = &mut voices[step as usize];
+        self.device.set_sound_volume(sound, volume);
+        self.device.set_sound_pitch(sound, pitch);
+        self.device.play_sound_multi(sound);
+    }
+    fn play_music(&mut self, music: Handle<Music>) {
+        if let Some((device, music)) = self.music_mut(music) {
+            device.play_music_stream(music);
+        }
+    }
+    fn stop_music(&mut self, music: Handle<Music>) {
+        if let Some((device, music)) = self.music_mut(music) {
+            device.stop_music_stream(music);
+        }
+    }
+    fn is_music_playing(&self, music: Handle<Music>) -> bool {
+        match self.music.get(music.index()) {
+            Some(Some(music)) => self.device.is_music_playing(music),
+            _ => false,
+        }
+    }
+    fn set_music_volume(&mut self, music: Handle<Music>, volume: f32) {
+        if let Some((device, music)) = self.music_mut(music) {
+            device.set_music_volume(music, volume);
+        }
+    }
+    fn set_music_pitch(&mut self, music: Handle<Music>, pitch: f32) {
+        if let Some((device, music)) = self.music_mut(music) {
+            device.set_music_pitch(music, pitch);
+        }
+    }
+    fn update_music(&mut self, music: Handle<Music>) {
+        if let Some((device, music)) = self.music_mut(music) {
+            device.update_music_stream(music);
+        }
+    }
+}
+
+//plays nothing but remembers which tracks would be playing
+pub struct NullAudio {
+    playing: Vec<Handle<Music>>,
+}
+
+impl NullAudio {
+    pub fn new() -> Self {
+        Self { playing: Vec::new() }
+    }
+}
+
+impl Audio for NullAudio {
+    fn is_enabled(&self) -> bool {
+        false
+    }
+    fn store_sound(&mut self, _sound: Handle<Sound>, _voices: Vec<Sound>) {}
+    fn store_music(&mut self, _music: Handle<Music>, _stream: Music) {}
+    fn play_sound(&mut self, _sound: Handle<Sound>, _volume: f32, _pitch: f32, _pan: f32) {}
+    fn play_music(&mut self, music: Handle<Music>) {
+        if (!self.playing.contains(&music)) {
+            self.playing.push(music);
+        }
+    }
+    fn stop_music(&mut self, music: Handle<Music>) {
+        self.playing.retain(|el| *el != music);
+    }
+    fn is_music_playing(&self, music: Handle<Music>) -> bool {
+        self.playing.contains(&music)
+    }
+    fn set_music_volume(&mut self, _music: Handle<Music>, _volume: f32) {}
+    fn set_music_pitch(&mut self, _music: Handle<Music>, _pitch: f32) {}
+    fn update_music(&mut self, _music: Handle<Music>) {}
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+pub enum AudioEvent {
+    Sound(Handle<Sound>),
+    MusicStarted(Handle<Music>),
+    MusicStopped(Handle<Music>),
+}
+
+//forwards everything to another backend and keeps a log of what was played
+#[cfg(test)]
+pub struct Recording<A: Audio> {
+    inner: A,
+    events: Vec<AudioEvent>,
+}
+
+#[cfg(test)]
+impl<A: Audio> Recording<A> {
+    pub fn new(inner: A) -> Self {
+        Self { inner, events: Vec::new() }
+    }
+    pub fn events(&self) -> &[AudioEvent] {
+        &self.events
+    }
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+impl<A: Audio> Audio for Recording<A> {
+    fn is_enabled(&self) -> bool {
+        self.inner.is_enabled()
+    }
+    fn store_sound(&mut self, sound: Handle<Sound>, voices: Vec<Sound>) {
+        self.inner.store_sound(sound, voices);
+    }
+    fn store_music(&mut self, music: Handle<Music>, stream: Music) {
+        self.inner.store_music(music, stream);
+    }
+    fn play_sound(&mut self, sound: Handle<Sound>, volume: f32, pitch: f32, pan: f32) {
+        self.events.push(AudioEvent::Sound(sound));
+        self.inner.play_sound(sound, volume, pitch, pan);
+    }
+    fn play_music(&mut self, music: Handle<Music>) {
+        self.events.push(AudioEvent::MusicStarted(music));
+        self.inner.play_music(music);
+    }
+    fn stop_music(&mut self, music: Handle<Music>) {
+        self.events.push(AudioEvent::MusicStopped(music));
+        self.inner.stop_music(music);
+    }
+    fn is_music_playing(&self, music: Handle<Music>) -> bool {
+        self.inner.is_music_playing(music)
+    }
+    fn set_music_volume(&mut self, music: Handle<Music>, volume: f32) {
+        self.inner.set_music_volume(music, volume);
+    }
+    fn set_music_pitch(&mut self, music: Handle<Music>, pitch: f32) {
+        self.inner.set_music_pitch(music, pitch);
+    }
+    fn update_music(&mut self, music: Handle<Music>) {
+        self.inner.update_music(music);
+    }
+}
@@ -0,0 +1,121 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::time::Instant;
+
+//time in milliseconds as seen by the game, it only moves when ticked
+pub struct GameClock {
+    //None for a clock that never reads the wall clock
+    last: Option<Instant>,
+    now: u64,
+    remainder: f32,
+    time_scale: f32,
+    paused: bool,
+}
+
+impl GameClock {
+    pub fn new() -> Self {
+        Self {
+            last: Some(Instant::now()),
+            now: 0,
+            remainder: 0.0,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+    //a clock that only moves with advance, so tests can step time exactly
+    #[cfg(test)]
+    pub fn manual() -> Self {
+        Self { last: None, ..Self::new() }
+    }
+    pub fn tick(&mut self) {
+        if let Some(last) = self.last {
+            let now = Instant::now();
+            let elapsed = now.duration_since(last).as_secs_f32() * 1000.0;
+            self.last = Some(now);
+            self.step(elapsed);
+        }
+    }
+    #[cfg(test)]
+    pub fn advance(&mut self, millis: u64) {
+        self.step(millis as f32);
+    }
+    fn step(&mut self, millis: f32) {
+        if (self.paused) {
+            return;
+        }
+        let scaled = millis * self.time_scale + self.remainder;
+        self.now += scaled.floor() as u64;
+        self.remainder = scaled.fract();
+    }
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+    pub fn elapsed_since(&self, time_point: u64) -> u64 {
+        self.now.saturating_sub(time_point)
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    #[cfg(test)]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_time_keeps_the_fractions() {
+        let mut clock = GameClock::manual();
+        clock.set_time_scale(0.25);
+        for _ in 0..10 {
+            clock.advance(1);
+        }
+        assert_eq!(clock.now(), 2);
+        clock.advance(2);
+        assert_eq!(clock.now(), 3);
+        clock.set_time_scale(-1.0);
+        clock.advance(100);
+        assert_eq!(clock.now(), 3);
+    }
+
+    #[test]
+    fn paused_time_is_lost() {
+        let mut clock = GameClock::manual();
+        clock.advance(10);
+        clock.pause();
+        clock.advance(500);
+        clock.tick();
+        assert_eq!(clock.now(), 10);
+        clock.resume();
+        clock.advance(5);
+        assert_eq!(clock.now(), 15);
+        assert_eq!(clock.elapsed_since(20), 0);
+    }
+}
@@ -15,68 +15,112 @@ Cheese Adventure a small and simple game
     You should have received a copy of the GNU General Public License
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-use std::thread::Thread;
-
-use crate::node::{
-    AnimatedButton, AnimatedNode, Direction, Gamestate, NumberDisplay, ResourceManager, VisibleNode,
-};
+use crate::animation::Animator;
+use crate::assets::{ResourceManager, Sprite};
+use crate::audio::Audio;
+use crate::clock::GameClock;
+use crate::input::{Controls, PlayerInput};
+use crate::lang::{Strings, LANG_DIR};
+use crate::music::MusicPlayer;
+use crate::node::{AnimatedNode, Direction, GameEvent, GameMode, Gamestate, VisibleNode};
+use crate::pointer::{Pointer, Viewport};
+use crate::scene::{Context, SceneStack, Transition};
+use crate::scenes::{AssetErrorsScene, MenuScene};
+use crate::score::{DailyHistory, HighScores, VersusScore};
+use crate::sfx::Sfx;
 use rand::prelude::*;
+use rand_pcg::Pcg32;
 use raylib::prelude::*;
-use KeyboardKey::*;
 
 pub const SCALE: i32 = 10;
+//how close a mouse has to get before a spider notices it
+const WAKE_DISTANCE: f32 = 16.0;
+//the music is at its calmest with every spider farther than DANGER_FAR and at its tensest under DANGER_NEAR
+const DANGER_NEAR: f32 = 6.0;
+const DANGER_FAR: f32 = 32.0;
+//awake spiders make a footstep sound every time they have walked this far
+const STEP_LENGTH: f32 = 2.0;
+
+//mice and spiders have one walking clip per direction
+fn walk_clip(direction: Direction) -> &'static str {
+    match direction {
+        Direction::UP => "walk_up",
+        Direction::LEFT => "walk_left",
+        Direction::DOWN => "walk_down",
+        Direction::RIGHT => "walk_right",
+    }
+}
 
+#[derive(Clone)]
 pub struct Mouse {
     node: AnimatedNode,
     direction: Direction,
     speed: f32,
+    alive: bool,
+    tint: Color,
 }
 
 impl Mouse {
     pub fn new(x: f32, y: f32, direction: Direction) -> Self {
         Self {
-            node: AnimatedNode::create_an(x, y, 9.0, 9.0, 8, 200),
+            node: AnimatedNode::create_an(x, y, 9.0, 9.0, walk_clip(direction)),
             direction: direction,
             speed: 0.2,
+            alive: true,
+            tint: Color::WHITE,
         }
     }
-    pub fn cycle(&mut self, handle: &mut RaylibHandle) {
-        if handle.is_key_down(KEY_A) {
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+    pub fn cycle(&mut self, input: PlayerInput) {
+        if input.left {
             if (self.node.pos.x > -3.0) {
                 self.direction = Direction::LEFT;
                 self.node.node.pos.x -= self.speed;
             }
-        } else if handle.is_key_down(KEY_D) {
+        } else if input.right {
             if (self.node.pos.x < 58.0) {
                 self.direction = Direction::RIGHT;
                 self.node.node.pos.x += self.speed;
             }
-        } else if handle.is_key_down(KEY_W) {
+        } else if input.up {
             if (self.node.pos.y < 58.0) {
                 self.direction = Direction::UP;
                 self.node.node.pos.y += self.speed;
             }
-        } else if handle.is_key_down(KEY_S) {
+        } else if input.down {
             if (self.node.pos.y > -3.0) {
                 self.direction = Direction::DOWN;
                 self.node.node.pos.y -= self.speed;
             }
         }
     }
-    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, texture: &Texture2D) {
-        self.node.cycle_animation();
-        self.node.draw(
-            ((self.direction as i32) as f32) * self.node.size.x,
-            (self.node.animation_step as f32) * self.node.size.x,
-            drawer,
-            texture,
-        );
+    pub fn draw(
+        &mut self,
+        drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+        sprite: &Sprite,
+        clock: &GameClock,
+        events: &mut Vec<(String, Vector2)>,
+    ) {
+        if (!self.alive) {
+            return;
+        }
+        self.node.draw_clip(walk_clip(self.direction), drawer, sprite, clock, self.tint, events);
     }
     pub fn get_pos(&self) -> &Vector2 {
         &self.node.pos
     }
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+    fn touches(&self, node: &VisibleNode) -> bool {
+        self.alive && node.collision(self.node.pos.x + 3.0, self.node.pos.y + 3.0, 3.0, 3.0)
+    }
 }
 
+#[derive(Clone)]
 pub struct Cheese {
     node: AnimatedNode,
 }
@@ -84,27 +128,32 @@ pub struct Cheese {
 impl Cheese {
     pub fn new(x: f32, y: f32) -> Self {
         Self {
-            node: AnimatedNode::create_an(x, y, 5.0, 5.0, 5, 200),
+            node: AnimatedNode::create_an(x, y, 5.0, 5.0, "idle"),
         }
     }
-    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, texture: &Texture2D) {
-        self.node.cycle_animation();
-        self.node.draw(
-            (self.node.animation_step as f32) * self.node.size.x,
-            0.0,
-            drawer,
-            texture,
-        )
+    pub fn draw(
+        &mut self,
+        drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+        sprite: &Sprite,
+        clock: &GameClock,
+        events: &mut Vec<(String, Vector2)>,
+    ) {
+        self.node.draw_clip("idle", drawer, sprite, clock, Color::WHITE, events);
     }
     pub fn is_available(&self) -> &bool {
         return &self.node.available;
     }
 }
 
+#[derive(Clone)]
 pub struct Spider {
     node: VisibleNode,
     direction: Direction,
     speed: f32,
+    controlled: bool,
+    awake: bool,
+    walked: f32,
+    animator: Animator,
 }
 
 impl Spider {
@@ -113,16 +162,64 @@ impl Spider {
             node: VisibleNode::create_vn(x, y, 6.0, 6.0),
             direction: direction,
             speed: 0.08,
+            controlled: false,
+            awake: false,
+            walked: 0.0,
+            animator: Animator::new(walk_clip(direction)),
         }
     }
-    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, texture: &Texture2D) {
-        self.node.draw(
-            (self.direction as i32 as f32) * self.node.size.x,
-            0.0,
+    pub fn draw(
+        &mut self,
+        drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+        sprite: &Sprite,
+        clock: &GameClock,
+        events: &mut Vec<(String, Vector2)>,
+    ) {
+        self.animator.play(walk_clip(self.direction));
+        if let Some(event) = self.animator.update(clock, &sprite.clips) {
+            events.push((event, self.node.pos.clone()));
+        }
+        let source = self.animator.source(&sprite.clips).unwrap_or(Rectangle {
+            x: 0.0,
+            y: 0.0,
+            width: self.node.size.x,
+            height: self.node.size.y,
+        });
+        self.node.draw_source(
+            source,
             drawer,
-            texture,
+            &sprite.texture,
+            if (self.controlled) {
+                Color { r: 230, g: 41, b: 55, a: 255 }
+            } else {
+                Color::WHITE
+            },
         )
     }
+    //versus mode: the spider follows the second player instead of chasing the mouse
+    pub fn steer(&mut self, input: PlayerInput) {
+        if input.left {
+            if (self.node.pos.x > 0.0) {
+                self.direction = Direction::LEFT;
+                self.node.pos.x -= self.speed;
+            }
+        } else if input.right {
+            if (self.node.pos.x < 58.0) {
+                self.direction = Direction::RIGHT;
+                self.node.pos.x += self.speed;
+            }
+        } else if input.up {
+            if (self.node.pos.y < 58.0) {
+                self.direction = Direction::UP;
+                self.node.pos.y += self.speed;
+            }
+        } else if input.down {
+            if (self.node.pos.y > 0.0) {
+                self.direction = Direction::DOWN;
+                self.node.pos.y -= self.speed;
+            }
+        }
+    }
     pub fn cycle(&mut self, target: &Vector2) {
         if (!self.node.available) {
             return;
@@ -165,6 +262,7 @@ impl Spider {
     }
 }
 
+#[derive(Clone)]
 pub struct Exit {
     node: AnimatedNode,
     direction: Direction,
@@ -173,29 +271,25 @@ pub struct Exit {
 impl Exit {
     pub fn new(x: f32, y: f32) -> Self {
         let mut this = Self {
-            node: AnimatedNode::create_an(x, y, 5.0, 7.0, 9, 50),
+            node: AnimatedNode::create_an(x, y, 5.0, 7.0, "idle_right"),
             direction: Direction::RIGHT,
         };
         this.node.node.available = false;
         return this;
     }
-    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, texture: &Texture2D) {
-        self.node.cycle_animation();
-        if (self.direction == Direction::RIGHT) {
-            self.node.draw(
-                (self.node.animation_step as f32) * self.node.size.x,
-                0.0,
-                drawer,
-                texture,
-            )
+    pub fn draw(
+        &mut self,
+        drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+        sprite: &Sprite,
+        clock: &GameClock,
+        events: &mut Vec<(String, Vector2)>,
+    ) {
+        let clip = if (self.direction == Direction::RIGHT) {
+            "idle_right"
         } else {
-            self.node.draw(
-                (self.node.animation_step as f32) * self.node.size.x,
-                self.node.size.y,
-                drawer,
-                texture,
-            )
-        }
+            "idle_left"
+        };
+        self.node.draw_clip(clip, drawer, sprite, clock, Color::WHITE, events);
     }
     pub fn is_available(&self) -> &bool {
         return &self.node.available;
@@ -205,21 +299,22 @@ impl Exit {
     }
 }
 
+#[derive(Clone)]
 pub struct Level {
     cheeses: Vec<Cheese>,
     spiders: Vec<Spider>,
     exit: Exit,
     points: u8,
     max_points: u8,
+    danger: f32,
 }
 
 impl Level {
     pub fn generate(
-        forbidden_x: &f32,
-        forbidden_y: &f32,
+        forbidden: &[Vector2],
         enemy_spaw_rate: &f32,
         cheese_spawn_rate: &f32,
-        rand: &mut ThreadRng,
+        rand: &mut Pcg32,
     ) -> Self {
         let mut cheeses = Vec::<Cheese>::new();
         let mut spiders = Vec::<Spider>::new();
@@ -241,8 +336,9 @@ impl Level {
                 } else if (prob_e <= *enemy_spaw_rate) {
                     let s_x = (x * 8) as f32;
                     let s_y = (y * 8) as f32;
-                    if ((s_x - forbidden_x).powf(2.0) + (s_y - forbidden_y).powf(2.0)).sqrt() > 20.0
-                    {
+                    if (forbidden.iter().all(|el| {
+                        ((s_x - el.x).powf(2.0) + (s_y - el.y).powf(2.0)).sqrt() > 20.0
+                    })) {
                         spiders.push(Spider::new(s_x, s_y, Direction::DOWN));
                     }
                 }
@@ -259,9 +355,54 @@ impl Level {
             exit,
             points: 0,
             max_points,
+            danger: 0.0,
         }
     }
-    pub fn cycle(&mut self, mouse: &Mouse, device : &mut RaylibAudio, pickup_sound : &Sound) -> Gamestate {
+    //spiders chase the closest mouse still alive
+    fn nearest_player(players: &[Mouse], pos: &Vector2) -> Option<Vector2> {
+        players
+            .iter()
+            .filter(|el| el.alive)
+            .map(|el| el.get_pos().clone())
+            .min_by(|a, b| {
+                let dist_a = (a.x - pos.x).powf(2.0) + (a.y - pos.y).powf(2.0);
+                let dist_b = (b.x - pos.x).powf(2.0) + (b.y - pos.y).powf(2.0);
+                dist_a.total_cmp(&dist_b)
+            })
+    }
+    //hands the spider farthest from the players over to player two, adding one if the level has none
+    pub fn take_control(&mut self, players: &[Vector2]) {
+        let distance = |pos: &Vector2| {
+            players
+                .iter()
+                .map(|el| (el.x - pos.x).powf(2.0) + (el.y - pos.y).powf(2.0))
+                .fold(f32::MAX, f32::min)
+        };
+        if (self.spiders.len() == 0) {
+            let corner = [
+                Vector2 { x: 0.0, y: 0.0 },
+                Vector2 { x: 58.0, y: 0.0 },
+                Vector2 { x: 0.0, y: 58.0 },
+                Vector2 { x: 58.0, y: 58.0 },
+            ]
+            .into_iter()
+            .max_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap();
+            self.spiders.push(Spider::new(corner.x, corner.y, Direction::DOWN));
+        }
+        let farthest = (0..self.spiders.len())
+            .max_by(|a, b| {
+                distance(self.spiders[*a].get_pos()).total_cmp(&distance(self.spiders[*b].get_pos()))
+            })
+            .unwrap();
+        self.spiders[farthest].controlled = true;
+    }
+    pub fn cycle(
+        &mut self,
+        players: &mut [Mouse],
+        spider_input: Option<PlayerInput>,
+        events: &mut Vec<GameEvent>,
+    ) -> Gamestate {
         {
             let spiders_positions: Vec<(Vector2, Vector2)> = self
                 .spiders
@@ -269,7 +410,29 @@ impl Level {
                 .map(|el| (el.get_pos().clone(), el.get_size().clone()))
                 .collect();
             for spider in &mut self.spiders {
-                spider.cycle(mouse.get_pos());
+                let start = spider.node.pos.clone();
+                if (spider.controlled) {
+                    if let Some(input) = spider_input {
+                        spider.steer(input);
+                    }
+                } else if let Some(target) = Level::nearest_player(players, spider.get_pos()) {
+                    let distance = ((target.x - spider.node.pos.x).powf(2.0)
+                        + (target.y - spider.node.pos.y).powf(2.0))
+                    .sqrt();
+                    if (!spider.awake && distance < WAKE_DISTANCE) {
+                        spider.awake = true;
+                        events.push(GameEvent::SpiderWoke(spider.node.pos.clone()));
+                    }
+                    spider.cycle(&target);
+                }
+                if (spider.awake || spider.controlled) {
+                    spider.walked += (spider.node.pos.x - start.x).abs()
+                        + (spider.node.pos.y - start.y).abs();
+                    if (spider.walked >= STEP_LENGTH) {
+                        spider.walked -= STEP_LENGTH;
+                        events.push(GameEvent::SpiderStep(spider.node.pos.clone()));
+                    }
+                }
                 for elm in &spiders_positions {
                     if (elm.0.x != spider.node.pos.x
                         && elm.0.y != spider.node.pos.y
@@ -286,36 +449,42 @@ impl Level {
                         }
                     }
                 }
-                if (spider
-                    .node
-                    .collision(mouse.node.pos.x + 3.0, mouse.node.pos.y + 3.0, 3.0, 3.0))
-                {
-                    return Gamestate::GameOver;
+                for player in players.iter_mut() {
+                    if (player.touches(&spider.node)) {
+                        player.alive = false;
+                        events.push(GameEvent::PlayerCaught(player.get_pos().clone()));
+                    }
                 }
             }
+            if (players.iter().all(|el| !el.alive)) {
+                return Gamestate::GameOver;
+            }
+            let nearest = self
+                .spiders
+                .iter()
+                .filter_map(|spider| {
+                    let target = Level::nearest_player(players, spider.get_pos())?;
+                    let pos = spider.get_pos();
+                    Some(((target.x - pos.x).powf(2.0) + (target.y - pos.y).powf(2.0)).sqrt())
+                })
+                .fold(f32::MAX, f32::min);
+            self.danger = ((DANGER_FAR - nearest) / (DANGER_FAR - DANGER_NEAR)).clamp(0.0, 1.0);
         }
         if (!self.exit.is_available() && self.points == self.max_points) {
             self.exit.activate();
+            events.push(GameEvent::ExitOpened(self.exit.node.pos.clone()));
         } else if (*self.exit.is_available()
-            && self
-                .exit
-                .node
-                .collision(mouse.node.pos.x + 3.0, mouse.node.pos.y + 3.0, 3.0, 3.0))
+            && players.iter().any(|el| el.touches(&self.exit.node)))
         {
             return Gamestate::NextLevel;
         } else {
             for cheese in &mut self.cheeses {
                 if (*cheese.is_available()
-                    && cheese.node.collision(
-                        mouse.node.pos.x + 3.0,
-                        mouse.node.pos.y + 3.0,
-                        mouse.node.size.x - 6.0,
-                        mouse.node.size.y - 6.0,
-                    ))
+                    && players.iter().any(|el| el.touches(&cheese.node)))
                 {
-                    device.play_sound(pickup_sound);
                     self.points += 1;
                     cheese.node.node.available = false;
+                    events.push(GameEvent::CheesePicked(cheese.node.pos.clone()));
                 }
             }
         }
@@ -323,151 +492,294 @@ impl Level {
     }
 }
 
-pub struct Game {
-    total_points: NumberDisplay,
-    volume : NumberDisplay,
+//everything that changes while playing, stepped only through inputs so two copies
+//fed with the same seed and inputs stay identical
+#[derive(Clone)]
+pub struct Simulation {
+    level: Level,
+    players: Vec<Mouse>,
+    rand_gen: Pcg32,
     enemy_spawn_rate: f32,
     cheese_spawn_rate: f32,
-    game_state: Gamestate,
     level_count: u32,
-    curr_level: Level,
-    character: Mouse,
-    rand_gen: ThreadRng,
-    texture_manager: ResourceManager,
-    screen_texture: RenderTexture2D,
-    start_button: AnimatedButton,
-    option_button: AnimatedButton,
-    retry_button: AnimatedButton,
-    extra_cheese: AnimatedButton,
-    back: AnimatedButton,
+    score: u32,
+    cheese_collected: u32,
+    tick: u64,
+    mode: GameMode,
+    mouse_player: usize,
+    level_start: Vec<Vector2>,
+    //daily runs and online matches put the players back on their spawn points every level,
+    //so the levels only depend on the seed and not on where the last one was left
+    fixed_starts: bool,
+    level_backup: Level,
+    //not part of the game state, the checksum ignores them
+    events: Vec<GameEvent>,
+    frame_events: Vec<(String, Vector2)>,
 }
-impl Game {
-    pub fn new(handle: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let screen_texture = handle
-            .load_render_texture(&thread, 64, 64)
-            .expect("Cannot create main texture");
 
-        let mut texture_manager = ResourceManager::new(handle, thread);
-        let mut rand_gen = thread_rng();
+impl Simulation {
+    pub fn new(mode: GameMode, mouse_player: usize, mut rand_gen: Pcg32) -> Self {
+        let mut players = vec![Mouse::new(10.0, 10.0, Direction::UP)];
+        if (mode == GameMode::Coop) {
+            players.push(
+                Mouse::new(50.0, 10.0, Direction::UP)
+                    .with_tint(Color { r: 255, g: 161, b: 0, a: 255 }),
+            );
+        } else if (mode == GameMode::Versus && mouse_player == 1) {
+            players[0].tint = Color { r: 255, g: 161, b: 0, a: 255 };
+        }
         let enemy_spawn_rate: f32 = 0.05;
         let cheese_spawn_rate: f32 = 0.02;
-        let character = Mouse {
-            node: AnimatedNode::create_an(10.0, 10.0, 9.0, 9.0, 8, 200),
-            direction: Direction::UP,
-            speed: 0.2,
-        };
-        let mut volume = NumberDisplay::new(40.0, 30.0);
-        volume.value = 100;
+        let level_start: Vec<Vector2> = players.iter().map(|el| el.get_pos().clone()).collect();
+        let mut level = Level::generate(
+            &level_start,
+            &enemy_spawn_rate,
+            &cheese_spawn_rate,
+            &mut rand_gen,
+        );
+        if (mode == GameMode::Versus) {
+            level.take_control(&level_start);
+        }
         Self {
-            total_points: NumberDisplay::new(1.0, 62.0),
-            volume,
+            level_backup: level.clone(),
+            level,
+            players,
+            rand_gen,
             enemy_spawn_rate,
             cheese_spawn_rate,
-            game_state: Gamestate::MainMenu,
             level_count: 0,
-            curr_level: Level::generate(
-                &character.node.pos.x,
-                &character.node.pos.y,
-                &enemy_spawn_rate,
-                &cheese_spawn_rate,
-                &mut rand_gen,
-            ),
-            character,
-            rand_gen,
-            texture_manager,
-            screen_texture,
-            start_button: AnimatedButton::new(3.0, 20.0, 30.0, 7.0),
-            option_button: AnimatedButton::new(3.0, 10.0, 36.0, 7.0),
-            retry_button: AnimatedButton::new(5.0, 20.0, 30.0, 7.0),
-            extra_cheese: AnimatedButton::new(0.0, 0.0, 64.0, 10.0),
-            back: AnimatedButton::new(5.0, 5.0, 25.0, 7.0),
+            score: 0,
+            cheese_collected: 0,
+            tick: 0,
+            mode,
+            mouse_player,
+            level_start,
+            fixed_starts: mode == GameMode::Daily,
+            events: vec![GameEvent::LevelStarted],
+            frame_events: Vec::new(),
         }
     }
-    pub fn update(&mut self, r_handle: &mut RaylibHandle, device : &mut RaylibAudio) {
-        if(!device.is_sound_playing(&self.texture_manager.theme)){
-            device.play_sound(&self.texture_manager.theme);
-        }
-        self.game_state = match self.game_state {
-            Gamestate::Play => {
-                self.character.cycle(r_handle);
-                self.curr_level.cycle(&self.character, device, &self.texture_manager.pickupsound)
-            }
-            Gamestate::NextLevel => {
-                self.level_count += 1;
-                self.total_points.value += self.curr_level.max_points as u32;
-                if (self.level_count % 8 == 0 && self.cheese_spawn_rate < 0.5) {
-                    self.cheese_spawn_rate += 0.05;
-                }
-                if (self.level_count % 5 == 0 && self.enemy_spawn_rate < 0.5) {
-                    self.enemy_spawn_rate += 0.05;
-                }
-                self.curr_level = Level::generate(
-                    &self.character.node.pos.x,
-                    &self.character.node.pos.y,
-                    &self.enemy_spawn_rate,
-                    &self.cheese_spawn_rate,
-                    &mut self.rand_gen,
-                );
-                if (self.curr_level.spiders.len() == 0) {
-                    self.extra_cheese.click();
-                }
-                Gamestate::Play
+    pub fn with_fixed_starts(mut self) -> Self {
+        self.fixed_starts = true;
+        self
+    }
+    //inputs are indexed by player, in versus the mouse player moves the mouse and the other the spider
+    pub fn step(&mut self, inputs: [PlayerInput; 2]) -> Gamestate {
+        self.tick += 1;
+        let points = self.level.points;
+        for (index, player) in self.players.iter_mut().enumerate() {
+            if (player.is_alive()) {
+                player.cycle(inputs[(index + self.mouse_player) % 2]);
             }
-            Gamestate::MainMenu => {
-                if (self.start_button.is_ready()) {
-                    Gamestate::Play
-                } else if (self.option_button.is_ready()) {
-                    Gamestate::OptionMenu
-                } else if (r_handle.is_key_released(KEY_A)) {
-                    device.play_sound(&self.texture_manager.clicksound);
-                    self.start_button.click();
-                    Gamestate::MainMenu
-                } else if (r_handle.is_key_released(KEY_S)) {
-                    device.play_sound(&self.texture_manager.clicksound);
-                    self.option_button.click();
-                    Gamestate::MainMenu
-                } else {
-                    Gamestate::MainMenu
-                }
+        }
+        let spider_input = if (self.mode == GameMode::Versus) {
+            Some(inputs[1 - self.mouse_player])
+        } else {
+            None
+        };
+        let state = self.level.cycle(&mut self.players, spider_input, &mut self.events);
+        self.cheese_collected += (self.level.points - points) as u32;
+        return state;
+    }
+    //the level stays as it is after step returns NextLevel until this is called
+    pub fn next_level(&mut self) {
+        self.level_count += 1;
+        self.score += self.level.max_points as u32;
+        if (self.level_count % 8 == 0 && self.cheese_spawn_rate < 0.5) {
+            self.cheese_spawn_rate += 0.05;
+        }
+        if (self.level_count % 5 == 0 && self.enemy_spawn_rate < 0.5) {
+            self.enemy_spawn_rate += 0.05;
+        }
+        //players caught during the level come back for the next one
+        for player in &mut self.players {
+            player.alive = true;
+        }
+        if (self.fixed_starts) {
+            for (player, start) in self.players.iter_mut().zip(self.level_start.iter()) {
+                player.node.node.pos = start.clone();
             }
-            Gamestate::GameOver => {
-                if (self.retry_button.is_ready()) {
-                    self.reset_game();
-                    Gamestate::Play
-                } else {
-                    if (self.total_points.get_pos().x == 1.0) {
-                        self.total_points.translate(20.0, 45.0);
-                        self.total_points.value *= self.level_count;
-                        self.total_points.escalate();
-                    }
-                    if (r_handle.is_key_released(KEY_A) && self.total_points.has_escalated()) {
-                        self.retry_button.click();
-                        device.play_sound(&self.texture_manager.clicksound);
-                    }
-                    Gamestate::GameOver
-                }
+        } else {
+            self.level_start = self.players.iter().map(|el| el.get_pos().clone()).collect();
+        }
+        self.level = Level::generate(
+            &self.level_start,
+            &self.enemy_spawn_rate,
+            &self.cheese_spawn_rate,
+            &mut self.rand_gen,
+        );
+        if (self.mode == GameMode::Versus) {
+            self.level.take_control(&self.level_start);
+        }
+        self.level_backup = self.level.clone();
+        self.events.push(GameEvent::LevelStarted);
+        if (!self.has_spiders()) {
+            self.events.push(GameEvent::PowerUp);
+        }
+    }
+    pub fn restart_level(&mut self) {
+        self.level = self.level_backup.clone();
+        for (player, start) in self.players.iter_mut().zip(self.level_start.iter()) {
+            player.node.node.pos = start.clone();
+            player.alive = true;
+        }
+        self.events.push(GameEvent::LevelStarted);
+    }
+    //the events since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+    pub fn set_events(&mut self, events: Vec<GameEvent>) {
+        self.events = events;
+    }
+    //the animation events raised while drawing, a sound id and where it happened
+    pub fn take_frame_events(&mut self) -> Vec<(String, Vector2)> {
+        std::mem::take(&mut self.frame_events)
+    }
+    pub fn draw(
+        &mut self,
+        drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+        resources: &ResourceManager,
+        clock: &GameClock,
+    ) {
+        let events = &mut self.frame_events;
+        self.level.exit.draw(drawer, &resources[resources.texture("exit")], clock, events);
+        let cheese_sprite = &resources[resources.texture("cheese")];
+        for cheese in &mut self.level.cheeses {
+            cheese.draw(drawer, cheese_sprite, clock, events);
+        }
+        let mouse_sprite = &resources[resources.texture("mouse")];
+        for player in &mut self.players {
+            player.draw(drawer, mouse_sprite, clock, events);
+        }
+        let spider_sprite = &resources[resources.texture("spider")];
+        for spider in &mut self.level.spiders {
+            spider.draw(drawer, spider_sprite, clock, events);
+        }
+    }
+    //how close the nearest spider is to a mouse, from 0 to 1, only the music listens to it
+    pub fn danger(&self) -> f32 {
+        self.level.danger
+    }
+    //where the sounds are heard from, every mouse still in the level
+    pub fn listeners(&self) -> Vec<Vector2> {
+        self.players
+            .iter()
+            .filter(|el| el.alive)
+            .map(|el| el.get_pos().clone())
+            .collect()
+    }
+    //true once the exit is open
+    pub fn is_cleared(&self) -> bool {
+        *self.level.exit.is_available()
+    }
+    pub fn has_spiders(&self) -> bool {
+        self.level.spiders.len() > 0
+    }
+    pub fn level_count(&self) -> u32 {
+        self.level_count
+    }
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+    pub fn cheese_collected(&self) -> u32 {
+        self.cheese_collected
+    }
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    //FNV-1a over the state that matters for gameplay, used to spot desyncs between peers
+    pub fn checksum(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut feed = |value: u64| {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
             }
-            Gamestate::OptionMenu => if(self.back.is_ready()){
-                Gamestate::MainMenu
-            }else{
-                if(r_handle.is_key_released(KEY_A)){
-                    device.play_sound(&self.texture_manager.clicksound);
-                    self.back.click();
-                }else if(r_handle.is_key_released(KEY_X)){
-                    if(self.volume.value < 100){
-                        self.volume.value += 10;
-                        device.set_master_volume((self.volume.value as f32) / 100.0);
-                    }
-                }else if(r_handle.is_key_released(KEY_Z)){
-                    if(self.volume.value > 0){
-                        self.volume.value -= 10;
-                        device.set_master_volume((self.volume.value as f32) / 100.0);
-                    }
-                }
-                Gamestate::OptionMenu
-            },
-            _ => Gamestate::Play,
         };
+        feed(self.tick);
+        feed(self.score as u64);
+        feed(self.level.points as u64);
+        for player in &self.players {
+            feed(player.get_pos().x.to_bits() as u64);
+            feed(player.get_pos().y.to_bits() as u64);
+            feed(player.alive as u64);
+        }
+        for spider in &self.level.spiders {
+            feed(spider.get_pos().x.to_bits() as u64);
+            feed(spider.get_pos().y.to_bits() as u64);
+        }
+        for cheese in &self.level.cheeses {
+            feed(*cheese.is_available() as u64);
+        }
+        return hash;
+    }
+}
+
+pub struct Game {
+    context: Context,
+    scenes: SceneStack,
+    screen_texture: RenderTexture2D,
+    hot_reload: bool,
+    debug_keys: bool,
+}
+impl Game {
+    pub fn new(handle: &mut RaylibHandle, thread: &RaylibThread, audio: &mut dyn Audio) -> Self {
+        let screen_texture = handle
+            .load_render_texture(&thread, 64, 64)
+            .expect("Cannot create main texture");
+
+        let mut context = Context {
+            resources: ResourceManager::new(handle, thread, audio),
+            clock: GameClock::new(),
+            ui_clock: GameClock::new(),
+            controls: [Controls::wasd(), Controls::arrows()],
+            pointer: Pointer::new(),
+            music: MusicPlayer::new(),
+            sfx: Sfx::new(),
+            strings: Strings::load(LANG_DIR, &Strings::system_language(LANG_DIR)),
+            high_scores: HighScores::load("highscores.txt"),
+            daily_history: DailyHistory::load("daily.txt"),
+            versus_score: VersusScore::new(),
+        };
+        let mut scenes = SceneStack::new(&mut context, Box::new(MenuScene::new()));
+        //the game still starts with fallbacks in place, but tell the player what is missing
+        if (!context.resources.errors().is_empty()) {
+            scenes.apply(&mut context, Transition::Push(Box::new(AssetErrorsScene::new())));
+        }
+        Self {
+            context,
+            scenes,
+            screen_texture,
+            hot_reload: false,
+            debug_keys: false,
+        }
+    }
+    //picks up edited textures and sounds while the game runs
+    pub fn with_hot_reload(mut self, hot_reload: bool) -> Self {
+        self.hot_reload = hot_reload;
+        self
+    }
+    //keys that are only there to help while working on the game
+    pub fn with_debug_keys(mut self, debug_keys: bool) -> Self {
+        self.debug_keys = debug_keys;
+        self
+    }
+    pub fn update(&mut self, r_handle: &mut RaylibHandle, thread: &RaylibThread, audio : &mut dyn Audio) {
+        self.context.clock.tick();
+        self.context.ui_clock.tick();
+        if(self.hot_reload){
+            self.context.resources.reload_changed(r_handle, thread, audio, &self.context.ui_clock);
+        }
+        //f1 slows the game down to check the animations, from full speed to a half and a quarter
+        if(self.debug_keys && r_handle.is_key_pressed(KeyboardKey::KEY_F1)){
+            let scale = self.context.clock.time_scale();
+            self.context.clock.set_time_scale(if(scale > 0.3) { scale / 2.0 } else { 1.0 });
+        }
+        let viewport = Viewport::fit(r_handle.get_screen_width(), r_handle.get_screen_height());
+        self.context.pointer.update(r_handle, &viewport);
+        self.context.music.update(audio, &self.context.ui_clock);
+        self.scenes.update(&mut self.context, r_handle, audio);
     }
     pub fn show(&mut self, drawer: &mut RaylibDrawHandle, thread: &RaylibThread) {
         let mut texture_drawer = drawer.begin_texture_mode(thread, &mut self.screen_texture);
@@ -477,97 +789,10 @@ impl Game {
             b: 98,
             a: 255,
         });
-        match self.game_state {
-            Gamestate::Play => {
-                self.curr_level
-                    .exit
-                    .draw(&mut texture_drawer, &self.texture_manager.exit);
-                for cheese in &mut self.curr_level.cheeses {
-                    cheese.draw(&mut texture_drawer, &self.texture_manager.cheese);
-                }
-                self.character
-                    .draw(&mut texture_drawer, &self.texture_manager.mouse);
-                for spider in &mut self.curr_level.spiders {
-                    spider.draw(&mut texture_drawer, &self.texture_manager.spider);
-                }
-                self.total_points.draw(&mut texture_drawer);
-                if (self.curr_level.spiders.len() == 0) {
-                    self.extra_cheese
-                        .draw(&mut texture_drawer, &self.texture_manager.ceasy)
-                }
-            }
-            Gamestate::MainMenu => {
-                texture_drawer.draw_texture_pro(
-                    &self.texture_manager.title,
-                    Rectangle {
-                        x: 0.0,
-                        y: 0.0,
-                        width: 64.0,
-                        height: -32.0,
-                    },
-                    Rectangle {
-                        x: 0.0,
-                        y: 32.0,
-                        width: 64.0,
-                        height: 32.0,
-                    },
-                    Vector2 { x: 0.0, y: 0.0 },
-                    0.0,
-                    Color::WHITE,
-                );
-                self.start_button
-                    .draw(&mut texture_drawer, &self.texture_manager.start);
-                self.option_button
-                    .draw(&mut texture_drawer, &self.texture_manager.options);
-            }
-            Gamestate::GameOver => {
-                texture_drawer.draw_texture_pro(
-                    &self.texture_manager.final_score,
-                    Rectangle {
-                        x: 0.0,
-                        y: 0.0,
-                        width: 64.0,
-                        height: -11.0,
-                    },
-                    Rectangle {
-                        x: 0.0,
-                        y: 52.0,
-                        width: 64.0,
-                        height: 11.0,
-                    },
-                    Vector2 { x: 0.0, y: 0.0 },
-                    0.0,
-                    Color::WHITE,
-                );
-                self.total_points.draw(&mut texture_drawer);
-                self.retry_button
-                    .draw(&mut texture_drawer, &self.texture_manager.retry);
-            },
-            Gamestate::OptionMenu => {
-                texture_drawer.draw_texture_pro(
-                    &self.texture_manager.volume_text,
-                    Rectangle {
-                        x: 0.0,
-                        y: 0.0,
-                        width: 64.0,
-                        height: -32.0,
-                    },
-                    Rectangle {
-                        x: 0.0,
-                        y: 32.0,
-                        width: 64.0,
-                        height: 32.0,
-                    },
-                    Vector2 { x: 0.0, y: 0.0 },
-                    0.0,
-                    Color::WHITE,
-                );
-                self.back.draw(&mut texture_drawer, &self.texture_manager.back);
-                self.volume.draw(&mut texture_drawer);
-            }
-            _ => (),
-        }
+        self.scenes.draw(&self.context, &mut texture_drawer);
         drop(texture_drawer);
+        let viewport = Viewport::fit(drawer.get_screen_width(), drawer.get_screen_height());
+        drawer.clear_background(Color::BLACK);
         drawer.draw_texture_pro(
             &self.screen_texture,
             Rectangle {
@@ -576,31 +801,152 @@ impl Game {
                 width: 64.0,
                 height: 64.0,
             },
-            Rectangle {
-                x: 0.0,
-                y: 0.0,
-                width: 64.0 * SCALE as f32,
-                height: 64.0 * SCALE as f32,
-            },
+            viewport.dest(),
             Vector2 { x: 0.0, y: 0.0 },
             0.0,
             Color::WHITE,
         )
     }
-    pub fn reset_game(&mut self) {
-        self.enemy_spawn_rate = 0.05;
-        self.cheese_spawn_rate = 0.02;
-        self.character.node.node.pos.x = 10.0;
-        self.character.node.node.pos.y = 10.0;
-        self.curr_level = Level::generate(
-            &self.character.node.pos.x,
-            &self.character.node.pos.y,
-            &self.enemy_spawn_rate,
-            &self.cheese_spawn_rate,
-            &mut self.rand_gen,
+    pub fn should_quit(&self) -> bool {
+        self.scenes.should_quit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::{SoundSettings, Sounds, SOUNDS};
+    use crate::audio::{AudioEvent, NullAudio, Recording};
+
+    fn sounds() -> Sounds {
+        let mut sounds = Sounds::new();
+        for id in SOUNDS {
+            sounds.insert(id, SoundSettings::new(), 100);
+        }
+        sounds
+    }
+
+    fn layout(sim: &Simulation) -> Vec<(f32, f32)> {
+        let level = &sim.level;
+        level
+            .cheeses
+            .iter()
+            .map(|el| el.node.pos.clone())
+            .chain(level.spiders.iter().map(|el| el.get_pos().clone()))
+            .chain(std::iter::once(level.exit.node.pos.clone()))
+            .map(|el| (el.x, el.y))
+            .collect()
+    }
+
+    #[test]
+    fn levels_only_depend_on_the_seed() {
+        let mut first = Simulation::new(GameMode::Daily, 0, Pcg32::seed_from_u64(20240305));
+        let mut second = first.clone();
+        let right = PlayerInput { right: true, ..PlayerInput::default() };
+        for _ in 0..40 {
+            first.step([right, PlayerInput::default()]);
+        }
+        for _ in 0..3 {
+            first.next_level();
+            second.next_level();
+            assert!(layout(&first) == layout(&second));
+        }
+    }
+
+    //where the players spawned, where they left the level and where they start the next one
+    fn next_level_starts(mut sim: Simulation) -> [Vec<(f32, f32)>; 3] {
+        let positions = |sim: &Simulation| {
+            sim.players
+                .iter()
+                .map(|el| (el.get_pos().x, el.get_pos().y))
+                .collect::<Vec<_>>()
+        };
+        let spawn = positions(&sim);
+        let right = PlayerInput { right: true, ..PlayerInput::default() };
+        for _ in 0..40 {
+            sim.step([right, right]);
+        }
+        let left = positions(&sim);
+        assert!(left != spawn);
+        sim.next_level();
+        [spawn, left, positions(&sim)]
+    }
+
+    #[test]
+    fn classic_levels_start_where_the_last_one_was_left() {
+        let [_, left, next] =
+            next_level_starts(Simulation::new(GameMode::Classic, 0, Pcg32::seed_from_u64(3)));
+        assert!(next == left);
+    }
+
+    #[test]
+    fn time_attack_levels_start_where_the_last_one_was_left() {
+        let [_, left, next] =
+            next_level_starts(Simulation::new(GameMode::TimeAttack, 0, Pcg32::seed_from_u64(3)));
+        assert!(next == left);
+    }
+
+    #[test]
+    fn coop_levels_start_where_the_last_one_was_left() {
+        let [_, left, next] =
+            next_level_starts(Simulation::new(GameMode::Coop, 0, Pcg32::seed_from_u64(3)));
+        assert!(next == left);
+    }
+
+    #[test]
+    fn versus_levels_start_where_the_last_one_was_left() {
+        let [_, left, next] =
+            next_level_starts(Simulation::new(GameMode::Versus, 1, Pcg32::seed_from_u64(3)));
+        assert!(next == left);
+    }
+
+    #[test]
+    fn daily_levels_start_on_the_spawn_points() {
+        let [spawn, _, next] =
+            next_level_starts(Simulation::new(GameMode::Daily, 0, Pcg32::seed_from_u64(3)));
+        assert!(next == spawn);
+    }
+
+    #[test]
+    fn online_levels_start_on_the_spawn_points() {
+        let sim = Simulation::new(GameMode::Coop, 0, Pcg32::seed_from_u64(3)).with_fixed_starts();
+        let [spawn, _, next] = next_level_starts(sim);
+        assert!(next == spawn);
+    }
+
+    #[test]
+    fn gameplay_events_are_heard() {
+        let sounds = sounds();
+        let clock = GameClock::manual();
+        let mut sfx = Sfx::new();
+        let mut audio = Recording::new(NullAudio::new());
+        let mut sim = Simulation::new(GameMode::Classic, 0, Pcg32::seed_from_u64(1));
+        let events = sim.take_events();
+        sfx.play_events(&sounds, &mut audio, &clock, &events, &sim.listeners());
+        assert_eq!(audio.events(), &[AudioEvent::Sound(sounds.handle("level_start"))]);
+
+        //one cheese right under the mouse, the exit opens the cycle after it is picked
+        audio.clear();
+        let mut players = vec![Mouse::new(10.0, 10.0, Direction::UP)];
+        let mut level = Level {
+            cheeses: vec![Cheese::new(12.0, 12.0)],
+            spiders: Vec::new(),
+            exit: Exit::new(59.0, 30.0),
+            points: 0,
+            max_points: 1,
+            danger: 0.0,
+        };
+        let mut events = Vec::new();
+        assert!(level.cycle(&mut players, None, &mut events) == Gamestate::Play);
+        assert!(level.cycle(&mut players, None, &mut events) == Gamestate::Play);
+        let listeners = [players[0].get_pos().clone()];
+        sfx.play_events(&sounds, &mut audio, &clock, &events, &listeners);
+        assert_eq!(
+            audio.events(),
+            &[
+                AudioEvent::Sound(sounds.handle("pickup")),
+                AudioEvent::Sound(sounds.handle("exit_open")),
+            ]
         );
-        self.total_points.translate(1.0, 62.0);
-        self.total_points.reset();
-        self.level_count = 0;
     }
 }
@@ -0,0 +1,99 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::clock::GameClock;
+use raylib::prelude::*;
+
+const EFFECT_DURATION: u64 = 600;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Effect {
+    Fade,
+    Dissolve,
+    Wipe,
+}
+
+//covers the screen, lets the owner swap what is under it at the midpoint, then uncovers it
+pub struct ScreenEffect {
+    effect: Effect,
+    start: u64,
+    swapped: bool,
+}
+
+impl ScreenEffect {
+    pub fn new(effect: Effect, clock: &GameClock) -> Self {
+        Self { effect, start: clock.now(), swapped: false }
+    }
+    //true only once, on the first call after the screen is fully covered
+    pub fn should_swap(&mut self, clock: &GameClock) -> bool {
+        if (self.swapped || clock.elapsed_since(self.start) < EFFECT_DURATION / 2) {
+            return false;
+        }
+        self.swapped = true;
+        return true;
+    }
+    pub fn is_done(&self, clock: &GameClock) -> bool {
+        self.swapped && clock.elapsed_since(self.start) >= EFFECT_DURATION
+    }
+    //0 when the screen is clear, 1 when it is completely covered
+    fn coverage(&self, clock: &GameClock) -> f32 {
+        let progress = (clock.elapsed_since(self.start) as f32 / EFFECT_DURATION as f32).min(1.0);
+        if (self.swapped) {
+            (2.0 - progress * 2.0).max(0.0)
+        } else {
+            (progress * 2.0).min(1.0)
+        }
+    }
+    pub fn draw(&self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, clock: &GameClock) {
+        let coverage = self.coverage(clock);
+        match self.effect {
+            Effect::Fade => {
+                drawer.draw_rectangle(
+                    0,
+                    0,
+                    64,
+                    64,
+                    Color { r: 0, g: 0, b: 0, a: (coverage * 255.0) as u8 },
+                );
+            }
+            Effect::Wipe => {
+                //comes in from the left and leaves on the right
+                let width = (coverage * 64.0).round() as i32;
+                let x = if (self.swapped) { 64 - width } else { 0 };
+                drawer.draw_rectangle(x, 0, width, 64, Color::BLACK);
+            }
+            Effect::Dissolve => {
+                //every pixel has a fixed threshold so they appear and disappear in the same order
+                for y in 0..64 {
+                    for x in 0..64 {
+                        if (pixel_threshold(x, y) < coverage) {
+                            drawer.draw_pixel(x, y, Color::BLACK);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn pixel_threshold(x: i32, y: i32) -> f32 {
+    let mut hash = (x as u32).wrapping_mul(73856093) ^ (y as u32).wrapping_mul(19349663);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1e995);
+    hash ^= hash >> 15;
+    (hash & 0xff) as f32 / 256.0
+}
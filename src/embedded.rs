@@ -0,0 +1,57 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::fs;
+use std::io;
+use std::path::Path;
+
+//filled by build.rs, empty unless the embed-assets feature is on
+include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
+
+pub fn get(path: &str) -> Option<&'static [u8]> {
+    FILES
+        .iter()
+        .find(|(name, _)| *name == path)
+        .map(|(_, bytes)| *bytes)
+}
+
+//a file on disk replaces the embedded copy, that is how mods override single assets
+pub fn is_overridden(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+pub fn read(path: &str) -> io::Result<Vec<u8>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(bytes),
+        Err(error) => get(path).map(|el| el.to_vec()).ok_or(error),
+    }
+}
+
+pub fn read_to_string(path: &str) -> io::Result<String> {
+    let bytes = read(path)?;
+    String::from_utf8(bytes).map_err(|el| io::Error::new(io::ErrorKind::InvalidData, el))
+}
+
+//names of the embedded files directly inside dir
+pub fn list(dir: &str) -> Vec<String> {
+    FILES
+        .iter()
+        .filter_map(|(name, _)| name.strip_prefix(dir)?.strip_prefix('/'))
+        .filter(|el| !el.contains('/'))
+        .map(|el| el.to_string())
+        .collect()
+}
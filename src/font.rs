@@ -0,0 +1,212 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use raylib::prelude::*;
+
+pub const GLYPH_WIDTH: f32 = 3.0;
+pub const GLYPH_HEIGHT: f32 = 5.0;
+const ADVANCE: f32 = GLYPH_WIDTH + 1.0;
+const LINE_HEIGHT: f32 = GLYPH_HEIGHT + 2.0;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+//anything the font does not know is drawn as a question mark
+const UNKNOWN: [u8; 5] = [0b110, 0b001, 0b010, 0b000, 0b010];
+
+//the glyphs are too small for accents, accented letters are drawn as the plain ones
+fn without_accent(character: char) -> char {
+    match character {
+        'à' | 'á' | 'À' | 'Á' => 'A',
+        'è' | 'é' | 'È' | 'É' => 'E',
+        'ì' | 'í' | 'Ì' | 'Í' => 'I',
+        'ò' | 'ó' | 'Ò' | 'Ó' => 'O',
+        'ù' | 'ú' | 'Ù' | 'Ú' => 'U',
+        _ => character,
+    }
+}
+
+//3x5 glyphs, one row per entry from the top, the highest of the 3 bits is the leftmost pixel
+fn glyph(character: char) -> [u8; 5] {
+    match without_accent(character).to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '*' => [0b101, 0b010, 0b101, 0b000, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        _ => UNKNOWN,
+    }
+}
+
+fn line_width(line: &str) -> f32 {
+    let count = line.chars().count() as f32;
+    if (count == 0.0) {
+        return 0.0;
+    }
+    count * ADVANCE - 1.0
+}
+
+//width of the longest line
+pub fn text_width(text: &str) -> f32 {
+    text.lines().map(line_width).fold(0.0, f32::max)
+}
+
+pub fn text_height(text: &str) -> f32 {
+    let count = text.lines().count().max(1) as f32;
+    count * LINE_HEIGHT - (LINE_HEIGHT - GLYPH_HEIGHT)
+}
+
+//the x of the first pixel of a line anchored at x
+fn line_left(line: &str, x: f32, align: Align) -> i32 {
+    (match align {
+        Align::Left => x,
+        Align::Center => (x - line_width(line) / 2.0).round(),
+        Align::Right => x - line_width(line),
+    }) as i32
+}
+
+//pos is the top of the first line, like for NumberDisplay, and x is the anchor chosen by align
+pub fn draw_text(
+    drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+    text: &str,
+    pos: Vector2,
+    align: Align,
+    color: Color,
+) {
+    let mut top = pos.y as i32;
+    for line in text.lines() {
+        let left = line_left(line, pos.x, align);
+        for (index, character) in line.chars().enumerate() {
+            let x = left + index as i32 * ADVANCE as i32;
+            for (row, bits) in glyph(character).iter().enumerate() {
+                for column in 0..3 {
+                    if (bits & (0b100 >> column) != 0) {
+                        drawer.draw_pixel(x + column, top - row as i32, color);
+                    }
+                }
+            }
+        }
+        top -= LINE_HEIGHT as i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::{key, Strings, LANG_DIR};
+
+    #[test]
+    fn glyphs_ignore_case_and_accents() {
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph('7'), [0b111, 0b001, 0b010, 0b010, 0b010]);
+        assert_eq!(glyph('è'), glyph('E'));
+        assert_eq!(glyph('Ù'), glyph('U'));
+        assert_eq!(glyph('€'), UNKNOWN);
+        assert_eq!(glyph('?'), UNKNOWN);
+    }
+
+    #[test]
+    fn lines_are_aligned_on_their_width() {
+        assert_eq!(line_width(""), 0.0);
+        assert_eq!(line_width("ABC"), 11.0);
+        //an accented letter is still a single glyph
+        assert_eq!(line_width("PIÙ"), 11.0);
+        assert_eq!(text_width("AB\nABCD"), 15.0);
+        assert_eq!(text_height("AB\nABCD"), 12.0);
+        assert_eq!(line_left("ABC", 32.0, Align::Left), 32);
+        assert_eq!(line_left("ABC", 32.0, Align::Center), 27);
+        assert_eq!(line_left("ABCD", 32.0, Align::Center), 25);
+        assert_eq!(line_left("ABC", 63.0, Align::Right), 52);
+    }
+
+    #[test]
+    fn every_shipped_string_has_its_glyphs() {
+        for language in Strings::available(LANG_DIR) {
+            let strings = Strings::load(LANG_DIR, &language);
+            for id in key::KEYS {
+                for character in strings
+                    .get(id)
+                    .chars()
+                    .filter(|el| *el != '\n' && *el != '?')
+                {
+                    assert!(
+                        glyph(character) != UNKNOWN,
+                        "{} in {} of {} has no glyph",
+                        character,
+                        id,
+                        language
+                    );
+                }
+            }
+        }
+    }
+}
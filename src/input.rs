@@ -0,0 +1,98 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use raylib::prelude::*;
+use GamepadButton::*;
+use KeyboardKey::*;
+
+const AXIS_DEADZONE: f32 = 0.5;
+
+#[derive(Copy, Clone, PartialEq, Default)]
+pub struct PlayerInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl PlayerInput {
+    pub fn to_bits(&self) -> u8 {
+        (self.up as u8) | (self.down as u8) << 1 | (self.left as u8) << 2 | (self.right as u8) << 3
+    }
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            up: bits & 1 != 0,
+            down: bits & 2 != 0,
+            left: bits & 4 != 0,
+            right: bits & 8 != 0,
+        }
+    }
+}
+
+//a key set plus the gamepad that can drive the same player
+#[derive(Copy, Clone)]
+pub struct Controls {
+    up: KeyboardKey,
+    down: KeyboardKey,
+    left: KeyboardKey,
+    right: KeyboardKey,
+    gamepad: i32,
+}
+
+impl Controls {
+    pub fn wasd() -> Self {
+        Self {
+            up: KEY_W,
+            down: KEY_S,
+            left: KEY_A,
+            right: KEY_D,
+            gamepad: 0,
+        }
+    }
+    pub fn arrows() -> Self {
+        Self {
+            up: KEY_UP,
+            down: KEY_DOWN,
+            left: KEY_LEFT,
+            right: KEY_RIGHT,
+            gamepad: 1,
+        }
+    }
+    pub fn read(&self, handle: &RaylibHandle) -> PlayerInput {
+        let mut input = PlayerInput {
+            up: handle.is_key_down(self.up),
+            down: handle.is_key_down(self.down),
+            left: handle.is_key_down(self.left),
+            right: handle.is_key_down(self.right),
+        };
+        if (handle.is_gamepad_available(self.gamepad)) {
+            let axis_x =
+                handle.get_gamepad_axis_movement(self.gamepad, GamepadAxis::GAMEPAD_AXIS_LEFT_X);
+            let axis_y =
+                handle.get_gamepad_axis_movement(self.gamepad, GamepadAxis::GAMEPAD_AXIS_LEFT_Y);
+            input.up |= axis_y < -AXIS_DEADZONE
+                || handle.is_gamepad_button_down(self.gamepad, GAMEPAD_BUTTON_LEFT_FACE_UP);
+            input.down |= axis_y > AXIS_DEADZONE
+                || handle.is_gamepad_button_down(self.gamepad, GAMEPAD_BUTTON_LEFT_FACE_DOWN);
+            input.left |= axis_x < -AXIS_DEADZONE
+                || handle.is_gamepad_button_down(self.gamepad, GAMEPAD_BUTTON_LEFT_FACE_LEFT);
+            input.right |= axis_x > AXIS_DEADZONE
+                || handle.is_gamepad_button_down(self.gamepad, GAMEPAD_BUTTON_LEFT_FACE_RIGHT);
+        }
+        return input;
+    }
+}
@@ -0,0 +1,361 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+//nesting deeper than this is not something an export does, it is refused before it can blow the stack
+const MAX_DEPTH: usize = 64;
+
+//just enough json to read the files other tools export, objects keep the order of their keys
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    //the value of key in an object, None for anything else
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|el| el.0 == key).map(|el| &el.1),
+            _ => None,
+        }
+    }
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { chars: text.chars().collect(), at: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_space();
+    if (parser.at < parser.chars.len()) {
+        return Err(parser.error("text after the end of the document"));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    //objects and arrays currently open
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, reason: &str) -> String {
+        format!("{} at character {}", reason, self.at)
+    }
+    fn skip_space(&mut self) {
+        while (self.at < self.chars.len() && self.chars[self.at].is_whitespace()) {
+            self.at += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.get(self.at).copied()
+    }
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if (self.peek() != Some(expected)) {
+            return Err(self.error(&format!("expected {}", expected)));
+        }
+        self.at += 1;
+        Ok(())
+    }
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        let end = self.at + word.len();
+        if (end > self.chars.len() || self.chars[self.at..end].iter().collect::<String>() != word) {
+            return Err(self.error("unexpected character"));
+        }
+        self.at = end;
+        Ok(value)
+    }
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('{') | Some('[') if (self.depth >= MAX_DEPTH) => {
+                Err(self.error("too deeply nested"))
+            }
+            Some('{') => {
+                self.depth += 1;
+                let object = self.object();
+                self.depth -= 1;
+                object
+            }
+            Some('[') => {
+                self.depth += 1;
+                let array = self.array();
+                self.depth -= 1;
+                array
+            }
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('n') => self.keyword("null", Value::Null),
+            Some(_) => self.number(),
+            None => Err(self.error("unexpected end of the document")),
+        }
+    }
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        if (self.peek() == Some('}')) {
+            self.at += 1;
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            if (self.peek() != Some('"')) {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            match self.peek() {
+                Some(',') => self.at += 1,
+                Some('}') => {
+                    self.at += 1;
+                    return Ok(Value::Object(entries));
+                }
+                _ => return Err(self.error("expected , or }")),
+            }
+        }
+    }
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        if (self.peek() == Some(']')) {
+            self.at += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek() {
+                Some(',') => self.at += 1,
+                Some(']') => {
+                    self.at += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error("expected , or ]")),
+            }
+        }
+    }
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let next = self.next_char()?;
+            match next {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escape = self.next_char()?;
+                    string.push(match escape {
+                        '"' | '\\' | '/' => escape,
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => self.unicode()?,
+                        _ => return Err(self.error(&format!("unknown escape \\{}", escape))),
+                    });
+                }
+                _ if (next < ' ') => return Err(self.error("control character in a string")),
+                _ => string.push(next),
+            }
+        }
+    }
+    fn next_char(&mut self) -> Result<char, String> {
+        let next = *self
+            .chars
+            .get(self.at)
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.at += 1;
+        Ok(next)
+    }
+    //the four hex digits after \u
+    fn hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .get(self.at)
+                .and_then(|el| el.to_digit(16))
+                .ok_or_else(|| self.error("\\u needs four hex digits"))?;
+            code = code * 16 + digit;
+            self.at += 1;
+        }
+        Ok(code)
+    }
+    //characters outside the basic plane come as a pair of surrogates, \ud83e\uddc0
+    fn unicode(&mut self) -> Result<char, String> {
+        let code = match self.hex()? {
+            high @ 0xd800..=0xdbff => {
+                if (self.chars.get(self.at) != Some(&'\\')
+                    || self.chars.get(self.at + 1) != Some(&'u'))
+                {
+                    return Err(self.error("unpaired surrogate"));
+                }
+                self.at += 2;
+                match self.hex()? {
+                    low @ 0xdc00..=0xdfff => 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                    _ => return Err(self.error("unpaired surrogate")),
+                }
+            }
+            0xdc00..=0xdfff => return Err(self.error("unpaired surrogate")),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid character"))
+    }
+    //-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.at;
+        if (self.chars.get(self.at) == Some(&'-')) {
+            self.at += 1;
+        }
+        match self.chars.get(self.at) {
+            Some('0') => self.at += 1,
+            Some('1'..='9') => {
+                self.digits();
+            }
+            _ => return Err(self.error("unexpected character")),
+        }
+        if (self.chars.get(self.at) == Some(&'.')) {
+            self.at += 1;
+            if (self.digits() == 0) {
+                return Err(self.error("expected a digit after the decimal point"));
+            }
+        }
+        if (matches!(self.chars.get(self.at), Some('e') | Some('E'))) {
+            self.at += 1;
+            if (matches!(self.chars.get(self.at), Some('+') | Some('-'))) {
+                self.at += 1;
+            }
+            if (self.digits() == 0) {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+        }
+        let text: String = self.chars[start..self.at].iter().collect();
+        text.parse::<f64>()
+            .map(Value::Number)
+            .map_err(|_| self.error("invalid number"))
+    }
+    //skips a run of digits and returns how long it was
+    fn digits(&mut self) -> usize {
+        let start = self.at;
+        while (self
+            .chars
+            .get(self.at)
+            .map_or(false, |el| el.is_ascii_digit()))
+        {
+            self.at += 1;
+        }
+        self.at - start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_follow_the_json_grammar() {
+        let number = |text: &str| parse(text).ok().and_then(|el| el.as_f64());
+        assert_eq!(number("0"), Some(0.0));
+        assert_eq!(number("-12"), Some(-12.0));
+        assert_eq!(number("1.5e2"), Some(150.0));
+        assert_eq!(number("2E-1"), Some(0.2));
+        for bad in ["+1", "01", "1.", ".5", "1e", "1-2", "--1", "1e+", "-"] {
+            assert!(parse(bad).is_err(), "{} should not parse", bad);
+        }
+        assert_eq!(
+            parse("[1-2]").unwrap_err(),
+            "expected , or ] at character 2"
+        );
+    }
+
+    #[test]
+    fn strings_decode_every_escape() {
+        let string = |text: &str| parse(text).map(|el| el.as_str().map(str::to_string));
+        assert_eq!(
+            string(r#""a\"\\\/\n\u00e8""#),
+            Ok(Some("a\"\\/\n\u{e8}".to_string()))
+        );
+        assert_eq!(
+            string(r#""\ud83e\uddc0""#),
+            Ok(Some("\u{1f9c0}".to_string()))
+        );
+        for bad in [
+            r#""\u12""#,
+            r#""\u12g4""#,
+            r#""\ud83e""#,
+            r#""\ud83e\u0041""#,
+            r#""\uddc0""#,
+            r#""\q""#,
+            "\"tab\there\"",
+            r#""open"#,
+        ] {
+            assert!(parse(bad).is_err(), "{} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn documents_keep_their_structure() {
+        let document = parse(r#" { "a": [true, null, {"b": "c"}], "a2": -0.5 } "#).unwrap();
+        let a = document.get("a").and_then(Value::as_array).unwrap();
+        assert_eq!(a[0], Value::Bool(true));
+        assert_eq!(a[1], Value::Null);
+        assert_eq!(a[2].get("b").and_then(Value::as_str), Some("c"));
+        assert_eq!(document.get("a2").and_then(Value::as_f64), Some(-0.5));
+        assert!(parse("{} {}").is_err());
+        assert!(parse(r#"{"a" 1}"#).is_err());
+        assert!(parse("[1,]").is_err());
+        assert!(parse("tru").is_err());
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&nested(100_000)).unwrap_err(),
+            format!("too deeply nested at character {}", MAX_DEPTH)
+        );
+    }
+}
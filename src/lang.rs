@@ -0,0 +1,194 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::embedded;
+use std::collections::HashMap;
+use std::fs;
+
+pub const LANG_DIR: &str = "lang";
+const FALLBACK: &str = "en";
+
+//declares a constant for every key the game looks up, and KEYS with all of them,
+//the scenes only look strings up through the constants so the check cannot miss one
+macro_rules! keys {
+    ($($name:ident = $key:literal,)*) => {
+        pub mod key {
+            $(pub const $name: &str = $key;)*
+            pub const KEYS: &[&str] = &[$($name),*];
+        }
+    };
+}
+
+keys! {
+    LANGUAGE_NAME = "language.name",
+    MENU_START = "menu.start",
+    MENU_OPTIONS = "menu.options",
+    MENU_DAILY = "menu.daily",
+    MENU_TIME = "menu.time",
+    MENU_COOP = "menu.coop",
+    MENU_VERSUS = "menu.versus",
+    MENU_ONLINE = "menu.online",
+    OPTIONS_TITLE = "options.title",
+    OPTIONS_MUSIC = "options.music",
+    OPTIONS_SOUNDS = "options.sounds",
+    OPTIONS_FULLSCREEN = "options.fullscreen",
+    COMMON_BACK = "common.back",
+    PAUSE_RESUME = "pause.resume",
+    PAUSE_RESTART = "pause.restart",
+    PAUSE_QUIT = "pause.quit",
+    GAMEOVER_TITLE = "gameover.title",
+    GAMEOVER_BEST = "gameover.best",
+    GAMEOVER_RETRY = "gameover.retry",
+    ROUND_NEXT = "round.next",
+    ROUND_PLAYER = "round.player",
+    LOBBY_HELP = "lobby.help",
+    LOBBY_ERROR = "lobby.error",
+    NET_WAITING = "net.waiting",
+    NET_REMATCH = "net.rematch",
+    NET_DESYNC = "net.desync",
+    ERRORS_TITLE = "errors.title",
+    ERRORS_CONTINUE = "errors.continue",
+}
+
+//one "key = value" per line, lines starting with # are comments and \n in a value breaks the line
+fn load_table(dir: &str, language: &str) -> Option<HashMap<String, String>> {
+    let content = embedded::read_to_string(&format!("{}/{}.txt", dir, language)).ok()?;
+    let mut table = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            table.insert(key.trim().to_string(), value.trim().replace("\\n", "\n"));
+        }
+    }
+    Some(table)
+}
+
+pub struct Strings {
+    language: String,
+    table: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Strings {
+    pub fn load(dir: &str, language: &str) -> Self {
+        let fallback = load_table(dir, FALLBACK).unwrap_or_default();
+        let table = match load_table(dir, language) {
+            Some(table) => table,
+            None => {
+                eprintln!("missing string table for {}, using {}", language, FALLBACK);
+                fallback.clone()
+            }
+        };
+        Self { language: language.to_string(), table, fallback }
+    }
+    //the language asked by the system if there is a table for it, english otherwise
+    pub fn system_language(dir: &str) -> String {
+        let wanted = std::env::var("LANG").unwrap_or_default();
+        let code: String = wanted
+            .chars()
+            .take_while(|el| el.is_ascii_alphabetic())
+            .collect();
+        let code = code.to_lowercase();
+        if (Strings::available(dir).contains(&code)) {
+            return code;
+        }
+        FALLBACK.to_string()
+    }
+    //language codes of the shipped tables, sorted
+    pub fn available(dir: &str) -> Vec<String> {
+        let mut languages: Vec<String> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|el| el.ok())
+                .filter_map(|el| {
+                    let name = el.file_name().into_string().ok()?;
+                    name.strip_suffix(".txt").map(|el| el.to_string())
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        for name in embedded::list(dir) {
+            if let Some(language) = name.strip_suffix(".txt") {
+                languages.push(language.to_string());
+            }
+        }
+        languages.sort();
+        languages.dedup();
+        return languages;
+    }
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+    //missing keys fall back to english and then to the key itself
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.table
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map(|el| el.as_str())
+            .unwrap_or(key)
+    }
+}
+
+//prints every key missing from the shipped tables or unknown to the game and returns how many there are
+pub fn check_tables(dir: &str) -> usize {
+    let languages = Strings::available(dir);
+    if (languages.is_empty()) {
+        eprintln!("no string tables found in {}", dir);
+        return 1;
+    }
+    let mut problems = 0;
+    for language in &languages {
+        let table = load_table(dir, language).unwrap_or_default();
+        for key in key::KEYS {
+            if (!table.contains_key(*key)) {
+                eprintln!("{}/{}.txt: missing {}", dir, language, key);
+                problems += 1;
+            }
+        }
+        for key in table.keys().filter(|el| !key::KEYS.contains(&el.as_str())) {
+            eprintln!("{}/{}.txt: {} is never used", dir, language, key);
+            problems += 1;
+        }
+    }
+    if (problems == 0) {
+        println!(
+            "{} string tables checked, all {} keys present",
+            languages.len(),
+            key::KEYS.len()
+        );
+    }
+    return problems;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_table_has_every_key() {
+        assert_eq!(check_tables(LANG_DIR), 0);
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english() {
+        let strings = Strings::load(LANG_DIR, "xx");
+        assert_eq!(strings.get(key::MENU_START), "START");
+        assert_eq!(strings.get("no.such.key"), "no.such.key");
+    }
+}
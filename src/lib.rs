@@ -15,5 +15,25 @@ Cheese Adventure a small and simple game
     You should have received a copy of the GNU General Public License
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+mod animation;
+mod aseprite;
+mod assets;
+mod audio;
+mod clock;
 mod node;
-mod drawable;
\ No newline at end of file
+mod pointer;
+mod scene;
+mod scenes;
+mod drawable;
+mod effect;
+mod embedded;
+mod font;
+mod input;
+mod json;
+mod lang;
+mod menu;
+mod music;
+mod net;
+mod score;
+mod sfx;
+mod synth;
\ No newline at end of file
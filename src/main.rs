@@ -15,31 +15,89 @@ Cheese Adventure a small and simple game
     You should have received a copy of the GNU General Public License
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+mod animation;
+mod aseprite;
+mod assets;
+mod audio;
+mod clock;
 mod drawable;
+mod effect;
+mod embedded;
+mod font;
+mod input;
+mod json;
+mod lang;
+mod menu;
+mod music;
+mod net;
 mod node;
+mod pointer;
+mod scene;
+mod scenes;
+mod score;
+mod sfx;
+mod synth;
 use raylib::prelude::*;
+use audio::{Audio, NullAudio};
 use drawable::{Game, SCALE};
 
 
 
 fn main() {
+    //checks the string tables and exits, meant for the build scripts
+    if std::env::args().any(|el| el == "--check-lang") {
+        let missing = lang::check_tables(lang::LANG_DIR);
+        std::process::exit(if missing == 0 { 0 } else { 1 });
+    }
+    //--render-sfx <preset> <file.wav> writes a synthesized sound to disk and exits
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(at) = args.iter().position(|el| el == "--render-sfx") {
+        match (args.get(at + 1), args.get(at + 2)) {
+            (Some(preset), Some(out)) => match synth::render_to_file(preset, out) {
+                Ok(()) => std::process::exit(0),
+                Err(reason) => {
+                    eprintln!("{}", reason);
+                    std::process::exit(1);
+                }
+            },
+            _ => {
+                eprintln!("usage: --render-sfx <preset> <file.wav>");
+                std::process::exit(1);
+            }
+        }
+    }
     let (mut r_handle, r_thread) = raylib::init()
         .size(64 * SCALE, 64 * SCALE)
         .title("Cheese Adventure")
+        .resizable()
         .build();
-    let mut audio_device = RaylibAudio::init_audio_device();
-    audio_device.set_master_volume(1.0);
-    let mut game = Game::new(&mut r_handle, &r_thread);
+    //--no-audio keeps the game silent without touching the sound card
+    let mut audio: Box<dyn Audio> = if std::env::args().any(|el| el == "--no-audio") {
+        Box::new(NullAudio::new())
+    } else {
+        audio::init()
+    };
+    //debug builds always watch the asset files, release builds only when asked
+    let hot_reload = cfg!(debug_assertions) || std::env::args().any(|el| el == "--hot-reload");
+    //the debug keys are on in debug builds and with --debug-keys
+    let debug_keys = cfg!(debug_assertions) || std::env::args().any(|el| el == "--debug-keys");
+    let mut game = Game::new(&mut r_handle, &r_thread, audio.as_mut())
+        .with_hot_reload(hot_reload)
+        .with_debug_keys(debug_keys);
 
     r_handle.set_target_fps(60);
+    //escape opens the pause menu instead of closing the window
+    r_handle.set_exit_key(None);
 
     //main app loop
-    while !r_handle.window_should_close() {
-        game.update(&mut r_handle, &mut audio_device);
+    while !r_handle.window_should_close() && !game.should_quit() {
+        game.update(&mut r_handle, &r_thread, audio.as_mut());
         //draw game
         let mut drawer = r_handle.begin_drawing(&r_thread);
         game.show(&mut drawer, &r_thread);
        
         //end draw game
     }
+    //the streams are closed before the resource manager deletes the copies they read from
+    drop(audio);
 }
@@ -0,0 +1,407 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::font::{self, Align};
+use crate::node::AnimatedButton;
+use crate::scene::Context;
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+const FOCUS_COLOR: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+const DISABLED_COLOR: Color = Color { r: 80, g: 80, b: 80, a: 255 };
+const IDLE_COLOR: Color = Color { r: 0, g: 167, b: 255, a: 255 };
+const TRACK_COLOR: Color = Color { r: 40, g: 46, b: 54, a: 255 };
+
+enum EntryKind {
+    Button,
+    Slider {
+        value: u32,
+        min: u32,
+        max: u32,
+        step: u32,
+        keys: Option<(KeyboardKey, KeyboardKey)>,
+    },
+    Toggle {
+        on: bool,
+    },
+}
+
+pub enum MenuEvent<A> {
+    Activated(A),
+    Changed(A, u32),
+    Toggled(A, bool),
+    Back,
+}
+
+pub struct MenuEntry<A> {
+    action: A,
+    button: AnimatedButton,
+    //key of the label in the string tables
+    label: Option<&'static str>,
+    color: Color,
+    kind: EntryKind,
+    key: Option<KeyboardKey>,
+    enabled: bool,
+}
+
+impl<A> MenuEntry<A> {
+    pub fn button(action: A, x: f32, y: f32, w: f32, h: f32, label: &'static str) -> Self {
+        Self::build(action, x, y, w, h, Some(label), EntryKind::Button)
+    }
+    pub fn slider(action: A, x: f32, y: f32, w: f32, h: f32) -> Self {
+        let kind = EntryKind::Slider { value: 0, min: 0, max: 100, step: 10, keys: None };
+        Self::build(action, x, y, w, h, None, kind)
+    }
+    pub fn toggle(action: A, x: f32, y: f32, size: f32) -> Self {
+        Self::build(
+            action,
+            x,
+            y,
+            size,
+            size,
+            None,
+            EntryKind::Toggle { on: false },
+        )
+    }
+    fn build(
+        action: A,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        label: Option<&'static str>,
+        kind: EntryKind,
+    ) -> Self {
+        Self {
+            action,
+            button: AnimatedButton::new(x, y, w, h),
+            label,
+            color: IDLE_COLOR,
+            kind,
+            key: None,
+            enabled: true,
+        }
+    }
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self.button.set_color(color);
+        self
+    }
+    //sliders and toggles show their label as text on their left
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+    pub fn with_key(mut self, key: KeyboardKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+    pub fn with_range(mut self, new_min: u32, new_max: u32, new_step: u32) -> Self {
+        if let EntryKind::Slider { value, min, max, step, .. } = &mut self.kind {
+            *min = new_min;
+            *max = new_max.max(new_min);
+            *step = new_step;
+            *value = (*value).clamp(*min, *max);
+        }
+        self
+    }
+    //keys that move a slider down and up even when it is not focused
+    pub fn with_slider_keys(mut self, decrease: KeyboardKey, increase: KeyboardKey) -> Self {
+        if let EntryKind::Slider { keys, .. } = &mut self.kind {
+            *keys = Some((decrease, increase));
+        }
+        self
+    }
+    pub fn disabled(mut self) -> Self {
+        self.set_enabled(false);
+        self
+    }
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.button.set_color(if (enabled) {
+            self.color
+        } else {
+            DISABLED_COLOR
+        });
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        let pos = *self.button.get_pos();
+        let size = *self.button.get_size();
+        let color = if (self.enabled) {
+            self.color
+        } else {
+            DISABLED_COLOR
+        };
+        match self.kind {
+            EntryKind::Button => {
+                let text = self.label.map_or("", |el| ctx.strings.get(el));
+                self.button.draw_label(drawer, text, &ctx.ui_clock);
+            }
+            EntryKind::Slider { value, min, max, .. } => {
+                let filled = (size.x * (value - min) as f32 / (max - min).max(1) as f32).round();
+                drawer.draw_rectangle(
+                    pos.x as i32,
+                    pos.y as i32,
+                    size.x as i32,
+                    size.y as i32,
+                    TRACK_COLOR,
+                );
+                drawer.draw_rectangle(
+                    pos.x as i32,
+                    pos.y as i32,
+                    filled as i32,
+                    size.y as i32,
+                    color,
+                );
+            }
+            EntryKind::Toggle { on } => {
+                if (on) {
+                    drawer.draw_rectangle(
+                        pos.x as i32,
+                        pos.y as i32,
+                        size.x as i32,
+                        size.y as i32,
+                        color,
+                    );
+                } else {
+                    drawer.draw_rectangle_lines(
+                        pos.x as i32,
+                        pos.y as i32,
+                        size.x as i32,
+                        size.y as i32,
+                        color,
+                    );
+                }
+            }
+        }
+        if let (EntryKind::Slider { .. } | EntryKind::Toggle { .. }, Some(label)) =
+            (&self.kind, self.label)
+        {
+            let top = pos.y + ((size.y + font::GLYPH_HEIGHT) / 2.0).floor() - 1.0;
+            font::draw_text(
+                drawer,
+                ctx.strings.get(label),
+                Vector2 { x: pos.x - 2.0, y: top },
+                Align::Right,
+                color,
+            );
+        }
+    }
+}
+
+//a list of entries walked with the arrow keys, in the order they were declared
+pub struct Menu<A> {
+    entries: Vec<MenuEntry<A>>,
+    focus: usize,
+    activating: Option<usize>,
+}
+
+impl<A: Copy + PartialEq> Menu<A> {
+    pub fn new(entries: Vec<MenuEntry<A>>) -> Self {
+        let focus = entries.iter().position(|el| el.enabled).unwrap_or(0);
+        Self { entries, focus, activating: None }
+    }
+    pub fn with_focus(mut self, action: A) -> Self {
+        if let Some(index) = self.index_of(action) {
+            self.focus = index;
+        }
+        self
+    }
+    fn index_of(&self, action: A) -> Option<usize> {
+        self.entries.iter().position(|el| el.action == action)
+    }
+    pub fn set_enabled(&mut self, action: A, enabled: bool) {
+        if let Some(index) = self.index_of(action) {
+            self.entries[index].set_enabled(enabled);
+            if (!enabled && self.focus == index) {
+                self.move_focus(1);
+            }
+        }
+    }
+    pub fn set_value(&mut self, action: A, new_value: u32) {
+        if let Some(index) = self.index_of(action) {
+            if let EntryKind::Slider { value, min, max, .. } = &mut self.entries[index].kind {
+                *value = new_value.clamp(*min, *max);
+            }
+        }
+    }
+    pub fn set_toggle(&mut self, action: A, new_on: bool) {
+        if let Some(index) = self.index_of(action) {
+            if let EntryKind::Toggle { on } = &mut self.entries[index].kind {
+                *on = new_on;
+            }
+        }
+    }
+    pub fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Option<MenuEvent<A>> {
+        //a confirmed button plays its animation before it fires and blocks the menu meanwhile
+        if let Some(index) = self.activating {
+            if (self.entries[index].button.is_ready()) {
+                self.activating = None;
+                return Some(MenuEvent::Activated(self.entries[index].action));
+            }
+            return None;
+        }
+        if let Some(index) = self.entry_under(ctx) {
+            if (ctx.pointer.has_moved() && self.focus != index) {
+                self.focus = index;
+                ctx.play_sound(audio, "menu_move");
+            }
+            let entry = &self.entries[index];
+            if let EntryKind::Slider { .. } = entry.kind {
+                if (ctx.pointer.is_down()) {
+                    self.focus = index;
+                    return self.slide_to(index, ctx);
+                }
+            } else if (entry.button.is_clicked(&ctx.pointer)) {
+                self.focus = index;
+                return self.press(index, ctx, audio);
+            }
+        }
+        if (handle.is_key_pressed(KEY_DOWN)) {
+            self.move_focus(1);
+            ctx.play_sound(audio, "menu_move");
+        } else if (handle.is_key_pressed(KEY_UP)) {
+            self.move_focus(-1);
+            ctx.play_sound(audio, "menu_move");
+        }
+        if (handle.is_key_released(KEY_ESCAPE)) {
+            return Some(MenuEvent::Back);
+        }
+        if (handle.is_key_released(KEY_ENTER) || handle.is_key_released(KEY_SPACE)) {
+            return self.press(self.focus, ctx, audio);
+        }
+        if (handle.is_key_pressed(KEY_LEFT)) {
+            return self.slide(self.focus, false);
+        } else if (handle.is_key_pressed(KEY_RIGHT)) {
+            return self.slide(self.focus, true);
+        }
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            if (!entry.enabled) {
+                continue;
+            }
+            if let Some(key) = entry.key {
+                if (handle.is_key_released(key)) {
+                    self.focus = index;
+                    return self.press(index, ctx, audio);
+                }
+            }
+            if let EntryKind::Slider { keys: Some((decrease, increase)), .. } = entry.kind {
+                if (handle.is_key_released(decrease) || handle.is_key_released(increase)) {
+                    self.focus = index;
+                    return self.slide(index, handle.is_key_released(increase));
+                }
+            }
+        }
+        None
+    }
+    fn entry_under(&self, ctx: &Context) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|el| el.enabled && el.button.is_hovered(&ctx.pointer))
+    }
+    fn move_focus(&mut self, delta: isize) {
+        let count = self.entries.len() as isize;
+        for _ in 0..count {
+            self.focus = (self.focus as isize + delta).rem_euclid(count) as usize;
+            if (self.entries[self.focus].enabled) {
+                return;
+            }
+        }
+    }
+    fn press(
+        &mut self,
+        index: usize,
+        ctx: &mut Context,
+        audio: &mut dyn Audio,
+    ) -> Option<MenuEvent<A>> {
+        let entry = &mut self.entries[index];
+        if (!entry.enabled) {
+            return None;
+        }
+        match &mut entry.kind {
+            EntryKind::Button => {
+                ctx.play_sound(audio, "click");
+                entry.button.click(&ctx.ui_clock);
+                self.activating = Some(index);
+                None
+            }
+            EntryKind::Toggle { on } => {
+                ctx.play_sound(audio, "click");
+                *on = !*on;
+                Some(MenuEvent::Toggled(entry.action, *on))
+            }
+            EntryKind::Slider { .. } => None,
+        }
+    }
+    fn slide(&mut self, index: usize, increase: bool) -> Option<MenuEvent<A>> {
+        let entry = &mut self.entries[index];
+        if (!entry.enabled) {
+            return None;
+        }
+        if let EntryKind::Slider { value, min, max, step, .. } = &mut entry.kind {
+            let next = if (increase) {
+                (*value + *step).min(*max)
+            } else {
+                value.saturating_sub(*step).max(*min)
+            };
+            if (next != *value) {
+                *value = next;
+                return Some(MenuEvent::Changed(entry.action, next));
+            }
+        }
+        None
+    }
+    //moves a slider to the step closest to the pointer
+    fn slide_to(&mut self, index: usize, ctx: &Context) -> Option<MenuEvent<A>> {
+        let entry = &mut self.entries[index];
+        let (pos, size) = (*entry.button.get_pos(), *entry.button.get_size());
+        let pointer_x = ctx.pointer.position()?.x;
+        if let EntryKind::Slider { value, min, max, step, .. } = &mut entry.kind {
+            let ratio = ((pointer_x - pos.x) / size.x).clamp(0.0, 1.0);
+            let steps = (ratio * (*max - *min) as f32 / (*step).max(1) as f32).round() as u32;
+            let next = (*min + steps * *step).min(*max);
+            if (next != *value) {
+                *value = next;
+                return Some(MenuEvent::Changed(entry.action, next));
+            }
+        }
+        None
+    }
+    pub fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        for entry in &mut self.entries {
+            entry.draw(ctx, drawer);
+        }
+        if let Some(entry) = self.entries.get(self.focus) {
+            let pos = entry.button.get_pos();
+            let size = entry.button.get_size();
+            drawer.draw_rectangle_lines(
+                pos.x as i32 - 1,
+                pos.y as i32 - 1,
+                size.x as i32 + 2,
+                size.y as i32 + 2,
+                FOCUS_COLOR,
+            );
+        }
+    }
+}
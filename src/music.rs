@@ -0,0 +1,180 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::assets::Handle;
+use crate::audio::Audio;
+use crate::clock::GameClock;
+use raylib::prelude::Music;
+
+//how long the old track takes to fade out while the new one fades in
+const CROSS_FADE: u64 = 1500;
+//how long the mix takes to go all the way from calm to tense, or back
+const MOOD_TIME: u64 = 800;
+//the music steps back once the exit is open, the level is as good as cleared
+const CLEARED_VOLUME: f32 = 0.4;
+//a track without a tense layer plays this much faster when the danger is highest
+const TENSE_TEMPO: f32 = 0.12;
+
+//a calm track and the layer that plays in sync with it and takes over as the danger rises
+#[derive(Copy, Clone, PartialEq)]
+struct Track {
+    calm: Handle<Music>,
+    tense: Option<Handle<Music>>,
+}
+
+impl Track {
+    fn layers(self) -> impl Iterator<Item = Handle<Music>> {
+        std::iter::once(self.calm).chain(self.tense)
+    }
+}
+
+//keeps one streamed track going and cross-fades when the scenes ask for another one
+pub struct MusicPlayer {
+    wanted: Option<Track>,
+    current: Option<Track>,
+    fading_out: Option<Track>,
+    fade_start: u64,
+    volume: f32,
+    //the values the scenes asked for, the mix follows them over MOOD_TIME
+    target_danger: f32,
+    cleared: bool,
+    danger: f32,
+    quiet: f32,
+    last_update: u64,
+}
+
+impl MusicPlayer {
+    pub fn new() -> Self {
+        Self {
+            wanted: None,
+            current: None,
+            fading_out: None,
+            fade_start: 0,
+            volume: 1.0,
+            target_danger: 0.0,
+            cleared: false,
+            danger: 0.0,
+            quiet: 1.0,
+            last_update: 0,
+        }
+    }
+    //the switch happens on the next update, asking for the current track does nothing
+    pub fn play(&mut self, track: Handle<Music>) {
+        self.play_layers(track, track);
+    }
+    //two ids on the same stream leave a single layer, the danger then speeds it up instead
+    pub fn play_layers(&mut self, calm: Handle<Music>, tense: Handle<Music>) {
+        let tense = Some(tense).filter(|el| *el != calm);
+        self.wanted = Some(Track { calm, tense });
+        self.set_mood(0.0, false);
+    }
+    //danger goes from 0 with no spider around to 1 with one on the mouse
+    pub fn set_mood(&mut self, danger: f32, cleared: bool) {
+        self.target_danger = danger.clamp(0.0, 1.0);
+        self.cleared = cleared;
+    }
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+    //streams have to be fed every frame, a track that is not fed stutters
+    pub fn update(&mut self, audio: &mut dyn Audio, clock: &GameClock) {
+        let step = clock.elapsed_since(self.last_update) as f32 / MOOD_TIME as f32;
+        self.last_update = clock.now();
+        self.danger = approach(self.danger, self.target_danger, step);
+        let quiet = if (self.cleared) { CLEARED_VOLUME } else { 1.0 };
+        self.quiet = approach(self.quiet, quiet, step);
+        if (self.wanted != self.current) {
+            //a layer both tracks use keeps playing, there is nothing to fade on it
+            let wanted = self.wanted;
+            let keep = |el: &Handle<Music>| {
+                wanted.map_or(false, |track| track.layers().any(|layer| layer == *el))
+            };
+            if let Some(track) = self.fading_out {
+                track
+                    .layers()
+                    .filter(|el| !keep(el))
+                    .for_each(|el| audio.stop_music(el));
+            }
+            let shared = self
+                .current
+                .map_or(false, |track| track.layers().any(|el| keep(&el)));
+            self.fading_out = self.current;
+            self.fade_start = clock.now();
+            if (shared) {
+                for layer in self.current.iter().flat_map(|el| el.layers()) {
+                    if (!keep(&layer)) {
+                        audio.stop_music(layer);
+                    }
+                }
+                self.fading_out = None;
+                self.fade_start = clock.now().saturating_sub(CROSS_FADE);
+            }
+            self.current = self.wanted;
+            for layer in self.current.iter().flat_map(|el| el.layers()) {
+                if (!audio.is_music_playing(layer)) {
+                    audio.play_music(layer);
+                }
+            }
+        }
+        let progress = (clock.elapsed_since(self.fade_start) as f32 / CROSS_FADE as f32).min(1.0);
+        if let Some(track) = self.current {
+            //a track that was reloaded or ran out somehow starts again, layers and all so they stay in sync
+            if (track.layers().any(|el| !audio.is_music_playing(el))) {
+                track.layers().for_each(|el| {
+                    audio.stop_music(el);
+                    audio.play_music(el);
+                });
+            }
+            self.mix(audio, track, progress);
+        }
+        if let Some(track) = self.fading_out {
+            if (progress >= 1.0) {
+                track.layers().for_each(|el| audio.stop_music(el));
+                self.fading_out = None;
+            } else {
+                self.mix(audio, track, 1.0 - progress);
+            }
+        }
+    }
+    fn mix(&self, audio: &mut dyn Audio, track: Track, fade: f32) {
+        let volume = self.volume * self.quiet * fade;
+        match track.tense {
+            Some(tense) => {
+                audio.set_music_volume(track.calm, volume * (1.0 - self.danger));
+                audio.set_music_volume(tense, volume * self.danger);
+                audio.set_music_pitch(track.calm, 1.0);
+            }
+            None => {
+                audio.set_music_volume(track.calm, volume);
+                audio.set_music_pitch(track.calm, 1.0 + TENSE_TEMPO * self.danger);
+            }
+        }
+        track.layers().for_each(|el| audio.update_music(el));
+    }
+}
+
+//moves value toward target by at most step
+fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if (value < target) {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
+    }
+}
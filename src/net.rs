@@ -0,0 +1,585 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::drawable::Simulation;
+use crate::input::PlayerInput;
+use crate::node::{GameMode, Gamestate};
+use rand::prelude::*;
+use rand_pcg::Pcg32;
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PORT: u16 = 7777;
+//local inputs are scheduled this many ticks in the future to hide part of the latency
+const INPUT_DELAY: usize = 2;
+//how far the simulation may run ahead of the last confirmed remote input
+const MAX_ROLLBACK: u64 = 12;
+const MAX_INPUTS_PER_PACKET: usize = 64;
+const CHECKSUM_INTERVAL: u64 = 30;
+const TIMEOUT: Duration = Duration::from_secs(5);
+const MAGIC: u8 = 0xCA;
+
+enum Packet {
+    Hello,
+    Welcome {
+        seed: u64,
+        mode: GameMode,
+    },
+    Input {
+        round: u16,
+        ack: u64,
+        start: u64,
+        inputs: Vec<u8>,
+        checksum_tick: u64,
+        checksum: u64,
+    },
+    Rematch {
+        round: u16,
+    },
+    Bye,
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let mut data = vec![MAGIC];
+        match self {
+            Packet::Hello => data.push(0),
+            Packet::Welcome { seed, mode } => {
+                data.push(1);
+                data.extend_from_slice(&seed.to_le_bytes());
+                data.push(if (*mode == GameMode::Versus) { 1 } else { 0 });
+            }
+            Packet::Input {
+                round,
+                ack,
+                start,
+                inputs,
+                checksum_tick,
+                checksum,
+            } => {
+                data.push(2);
+                data.extend_from_slice(&round.to_le_bytes());
+                data.extend_from_slice(&ack.to_le_bytes());
+                data.extend_from_slice(&start.to_le_bytes());
+                data.extend_from_slice(&checksum_tick.to_le_bytes());
+                data.extend_from_slice(&checksum.to_le_bytes());
+                data.push(inputs.len() as u8);
+                data.extend_from_slice(inputs);
+            }
+            Packet::Rematch { round } => {
+                data.push(3);
+                data.extend_from_slice(&round.to_le_bytes());
+            }
+            Packet::Bye => data.push(4),
+        }
+        return data;
+    }
+    fn decode(data: &[u8]) -> Option<Packet> {
+        if (data.len() < 2 || data[0] != MAGIC) {
+            return None;
+        }
+        let body = &data[2..];
+        let u16_at = |at: usize| -> Option<u16> {
+            Some(u16::from_le_bytes(body.get(at..at + 2)?.try_into().ok()?))
+        };
+        let u64_at = |at: usize| -> Option<u64> {
+            Some(u64::from_le_bytes(body.get(at..at + 8)?.try_into().ok()?))
+        };
+        match data[1] {
+            0 => Some(Packet::Hello),
+            1 => Some(Packet::Welcome {
+                seed: u64_at(0)?,
+                mode: if (*body.get(8)? == 1) {
+                    GameMode::Versus
+                } else {
+                    GameMode::Coop
+                },
+            }),
+            2 => {
+                let count = *body.get(34)? as usize;
+                Some(Packet::Input {
+                    round: u16_at(0)?,
+                    ack: u64_at(2)?,
+                    start: u64_at(10)?,
+                    checksum_tick: u64_at(18)?,
+                    checksum: u64_at(26)?,
+                    inputs: body.get(35..35 + count)?.to_vec(),
+                })
+            }
+            3 => Some(Packet::Rematch { round: u16_at(0)? }),
+            4 => Some(Packet::Bye),
+            _ => None,
+        }
+    }
+}
+
+//one side of an online match: the host is player one, whoever joins is player two
+pub struct NetMatch {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    host: bool,
+    connected: bool,
+    disconnected: bool,
+    last_heard: Instant,
+    mode: GameMode,
+    seed: u64,
+    round: u16,
+    sim: Simulation,
+    //states before each tick that could still be rolled back, oldest first
+    snapshots: VecDeque<Simulation>,
+    local_inputs: Vec<PlayerInput>,
+    remote_inputs: Vec<PlayerInput>,
+    //remote inputs used for every simulated tick, confirmed or predicted
+    used_remote: Vec<PlayerInput>,
+    remote_ack: u64,
+    over_tick: Option<u64>,
+    checksums: VecDeque<(u64, u64)>,
+    remote_checksum: Option<(u64, u64)>,
+    //the first tick the peers disagreed on, the match cannot go on after it
+    desynced: Option<u64>,
+    rematch: bool,
+    remote_rematch: bool,
+}
+
+impl NetMatch {
+    pub fn host(port: u16, mode: GameMode) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        Ok(Self::create(socket, None, true, mode, StdRng::from_entropy().gen()))
+    }
+    pub fn join(address: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        Ok(Self::create(socket, Some(address), false, GameMode::Coop, 0))
+    }
+    fn create(
+        socket: UdpSocket,
+        peer: Option<SocketAddr>,
+        host: bool,
+        mode: GameMode,
+        seed: u64,
+    ) -> Self {
+        //the socket is polled once per frame, it must never block the game loop
+        socket.set_nonblocking(true).ok();
+        Self {
+            socket,
+            peer,
+            host,
+            connected: false,
+            disconnected: false,
+            last_heard: Instant::now(),
+            mode,
+            seed,
+            round: 0,
+            sim: Simulation::new(mode, 0, Pcg32::seed_from_u64(seed)).with_fixed_starts(),
+            snapshots: VecDeque::new(),
+            local_inputs: vec![PlayerInput::default(); INPUT_DELAY],
+            remote_inputs: Vec::new(),
+            used_remote: Vec::new(),
+            remote_ack: 0,
+            over_tick: None,
+            checksums: VecDeque::new(),
+            remote_checksum: None,
+            desynced: None,
+            rematch: false,
+            remote_rematch: false,
+        }
+    }
+    fn start_round(&mut self) {
+        let mouse_player = if (self.mode == GameMode::Versus) {
+            (self.round % 2) as usize
+        } else {
+            0
+        };
+        self.sim = Simulation::new(
+            self.mode,
+            mouse_player,
+            Pcg32::seed_from_u64(self.seed.wrapping_add(self.round as u64)),
+        )
+        .with_fixed_starts();
+        self.snapshots.clear();
+        self.local_inputs = vec![PlayerInput::default(); INPUT_DELAY];
+        self.remote_inputs.clear();
+        self.used_remote.clear();
+        self.remote_ack = 0;
+        self.over_tick = None;
+        self.checksums.clear();
+        self.remote_checksum = None;
+        self.desynced = None;
+        self.rematch = false;
+        self.remote_rematch = false;
+    }
+    pub fn update(&mut self, local: PlayerInput) {
+        let rollback_to = self.receive();
+        if (self.connected && self.last_heard.elapsed() > TIMEOUT) {
+            self.disconnected = true;
+        }
+        if (!self.connected) {
+            if (!self.host) {
+                self.send(&Packet::Hello);
+            }
+            return;
+        }
+        if (self.rematch && self.remote_rematch) {
+            self.round += 1;
+            self.start_round();
+        }
+        if (self.finished().is_none() && self.desynced.is_none()) {
+            if let Some(tick) = rollback_to {
+                self.rollback(tick);
+            }
+            self.advance(local);
+            self.trim_snapshots();
+            self.store_checksum();
+        }
+        self.send_inputs();
+        if (self.rematch) {
+            self.send(&Packet::Rematch { round: self.round });
+        }
+    }
+    fn receive(&mut self) -> Option<u64> {
+        let mut rollback_to: Option<u64> = None;
+        let mut buffer = [0u8; 512];
+        while let Ok((size, address)) = self.socket.recv_from(&mut buffer) {
+            if (self.peer.is_some() && self.peer != Some(address)) {
+                continue;
+            }
+            let packet = match Packet::decode(&buffer[..size]) {
+                Some(packet) => packet,
+                None => continue,
+            };
+            self.last_heard = Instant::now();
+            match packet {
+                Packet::Hello => {
+                    if (self.host) {
+                        if (!self.connected) {
+                            self.peer = Some(address);
+                            self.connected = true;
+                            self.start_round();
+                        }
+                        self.send(&Packet::Welcome {
+                            seed: self.seed,
+                            mode: self.mode,
+                        });
+                    }
+                }
+                Packet::Welcome { seed, mode } => {
+                    if (!self.host && !self.connected) {
+                        self.seed = seed;
+                        self.mode = mode;
+                        self.connected = true;
+                        self.start_round();
+                    }
+                }
+                Packet::Input {
+                    round,
+                    ack,
+                    start,
+                    inputs,
+                    checksum_tick,
+                    checksum,
+                } => {
+                    //the peer already moved on to the rematch
+                    if (round == self.round.wrapping_add(1) && self.finished().is_some()) {
+                        self.round = round;
+                        self.start_round();
+                        rollback_to = None;
+                    }
+                    if (round != self.round) {
+                        continue;
+                    }
+                    self.remote_ack = self.remote_ack.max(ack);
+                    for (offset, bits) in inputs.iter().enumerate() {
+                        let tick = start + offset as u64;
+                        if (tick != self.remote_inputs.len() as u64) {
+                            continue;
+                        }
+                        let input = PlayerInput::from_bits(*bits);
+                        if let Some(used) = self.used_remote.get(tick as usize) {
+                            if (*used != input) {
+                                rollback_to = Some(rollback_to.map_or(tick, |el| el.min(tick)));
+                            }
+                        }
+                        self.remote_inputs.push(input);
+                    }
+                    self.remote_checksum = Some((checksum_tick, checksum));
+                }
+                Packet::Rematch { round } => {
+                    if (round == self.round) {
+                        self.remote_rematch = true;
+                    }
+                }
+                Packet::Bye => self.disconnected = true,
+            }
+        }
+        self.check_desync();
+        return rollback_to;
+    }
+    fn inputs_for(&self, tick: u64) -> ([PlayerInput; 2], PlayerInput) {
+        let local = self.local_inputs[tick as usize];
+        //without the real input assume the remote player kept doing the same thing
+        let remote = match self.remote_inputs.get(tick as usize) {
+            Some(input) => *input,
+            None => self.remote_inputs.last().copied().unwrap_or_default(),
+        };
+        let inputs = if (self.host) {
+            [local, remote]
+        } else {
+            [remote, local]
+        };
+        return (inputs, remote);
+    }
+    fn simulate_tick(&mut self) {
+        let tick = self.sim.tick();
+        let (inputs, remote) = self.inputs_for(tick);
+        self.snapshots.push_back(self.sim.clone());
+        self.used_remote.truncate(tick as usize);
+        self.used_remote.push(remote);
+        let state = self.sim.step(inputs);
+        if (state == Gamestate::NextLevel) {
+            self.sim.next_level();
+        } else if (state == Gamestate::GameOver && self.over_tick.is_none()) {
+            self.over_tick = Some(self.sim.tick());
+        }
+    }
+    fn rollback(&mut self, tick: u64) {
+        let target = self.sim.tick();
+        let index = match self.snapshots.iter().position(|el| el.tick() == tick) {
+            Some(index) => index,
+            None => return,
+        };
+        let events = self.sim.take_events();
+        self.sim = self.snapshots[index].clone();
+        self.snapshots.truncate(index);
+        if let Some(over_tick) = self.over_tick {
+            if (over_tick > tick) {
+                self.over_tick = None;
+            }
+        }
+        while (self.sim.tick() < target && self.over_tick.is_none()) {
+            self.simulate_tick();
+        }
+        //the ticks played again already made their sounds the first time
+        self.sim.set_events(events);
+    }
+    fn advance(&mut self, local: PlayerInput) {
+        let tick = self.sim.tick();
+        //stall instead of predicting too far ahead of the peer
+        if (tick >= self.remote_inputs.len() as u64 + MAX_ROLLBACK) {
+            return;
+        }
+        self.local_inputs.push(local);
+        if (self.over_tick.is_none()) {
+            self.simulate_tick();
+        }
+    }
+    fn trim_snapshots(&mut self) {
+        let confirmed = self.remote_inputs.len() as u64;
+        while let Some(snapshot) = self.snapshots.front() {
+            if (snapshot.tick() >= confirmed) {
+                break;
+            }
+            self.snapshots.pop_front();
+        }
+    }
+    //the state after a tick is final once the remote inputs up to it are known
+    fn store_checksum(&mut self) {
+        let confirmed = (self.remote_inputs.len() as u64).min(self.sim.tick());
+        if (confirmed == 0 || confirmed % CHECKSUM_INTERVAL != 0) {
+            return;
+        }
+        if (self.checksums.back().map_or(false, |el| el.0 >= confirmed)) {
+            return;
+        }
+        let checksum = if (self.sim.tick() == confirmed) {
+            self.sim.checksum()
+        } else {
+            match self.snapshots.iter().find(|el| el.tick() == confirmed) {
+                Some(snapshot) => snapshot.checksum(),
+                None => return,
+            }
+        };
+        self.checksums.push_back((confirmed, checksum));
+        if (self.checksums.len() > 16) {
+            self.checksums.pop_front();
+        }
+        self.check_desync();
+    }
+    fn check_desync(&mut self) {
+        if let Some((tick, checksum)) = self.remote_checksum {
+            if let Some(local) = self.checksums.iter().find(|el| el.0 == tick) {
+                if (local.1 != checksum && self.desynced.is_none()) {
+                    self.desynced = Some(tick);
+                }
+            }
+        }
+    }
+    fn send_inputs(&mut self) {
+        let start = self.remote_ack.min(self.local_inputs.len() as u64);
+        let end = self
+            .local_inputs
+            .len()
+            .min(start as usize + MAX_INPUTS_PER_PACKET);
+        let (checksum_tick, checksum) = self.checksums.back().copied().unwrap_or((0, 0));
+        self.send(&Packet::Input {
+            round: self.round,
+            ack: self.remote_inputs.len() as u64,
+            start,
+            inputs: self.local_inputs[start as usize..end]
+                .iter()
+                .map(|el| el.to_bits())
+                .collect(),
+            checksum_tick,
+            checksum,
+        });
+    }
+    fn send(&self, packet: &Packet) {
+        if let Some(peer) = self.peer {
+            self.socket.send_to(&packet.encode(), peer).ok();
+        }
+    }
+    pub fn request_rematch(&mut self) {
+        if (self.finished().is_some()) {
+            self.rematch = true;
+        }
+    }
+    pub fn leave(&mut self) {
+        self.send(&Packet::Bye);
+    }
+    //game over only counts once every input leading to it has been confirmed
+    pub fn finished(&self) -> Option<Gamestate> {
+        match self.over_tick {
+            Some(tick) if (tick <= self.remote_inputs.len() as u64) => Some(Gamestate::GameOver),
+            _ => None,
+        }
+    }
+    pub fn simulation(&self) -> &Simulation {
+        &self.sim
+    }
+    pub fn simulation_mut(&mut self) -> &mut Simulation {
+        &mut self.sim
+    }
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+    pub fn desynced(&self) -> Option<u64> {
+        self.desynced
+    }
+    pub fn is_waiting_rematch(&self) -> bool {
+        self.rematch
+    }
+}
+
+//address and port typed in the lobby before hosting or joining
+pub struct Lobby {
+    pub address: String,
+    pub port: String,
+    pub editing_port: bool,
+    pub mode: GameMode,
+    pub error: bool,
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Self {
+            address: String::from("127.0.0.1"),
+            port: DEFAULT_PORT.to_string(),
+            editing_port: false,
+            mode: GameMode::Coop,
+            error: false,
+        }
+    }
+    pub fn type_char(&mut self, character: char) {
+        if (self.editing_port) {
+            if (character.is_ascii_digit() && self.port.len() < 5) {
+                self.port.push(character);
+            }
+        } else if ((character.is_ascii_digit() || character == '.') && self.address.len() < 15) {
+            self.address.push(character);
+        }
+    }
+    pub fn erase(&mut self) {
+        if (self.editing_port) {
+            self.port.pop();
+        } else {
+            self.address.pop();
+        }
+    }
+    pub fn port(&self) -> u16 {
+        self.port.parse().unwrap_or(DEFAULT_PORT)
+    }
+    pub fn target(&self) -> Option<SocketAddr> {
+        format!("{}:{}", self.address, self.port()).parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    //each player holds a direction for a few ticks, changing at a different pace than the other
+    fn input(player: u64, frame: u64) -> PlayerInput {
+        PlayerInput::from_bits(1 << ((frame / (5 + player * 2) + player) % 4))
+    }
+
+    #[test]
+    fn peers_agree_after_rollbacks() {
+        let mut host = NetMatch::host(0, GameMode::Coop).unwrap();
+        host.seed = 7;
+        let port = host.socket.local_addr().unwrap().port();
+        let mut guest = NetMatch::join(SocketAddr::from(([127, 0, 0, 1], port))).unwrap();
+        let mut mispredicted = 0;
+        for frame in 0..700 {
+            let (confirmed, used) = (host.remote_inputs.len(), host.used_remote.clone());
+            host.update(input(0, frame));
+            //a remote input that arrived after its tick was simulated with a different guess
+            mispredicted += (confirmed..host.remote_inputs.len().min(used.len()))
+                .filter(|el| used[*el] != host.remote_inputs[*el])
+                .count();
+            //the guest skips frames so the host runs ahead on predicted inputs
+            if (frame >= 600) {
+                guest.update(PlayerInput::default());
+            } else if (frame % 3 != 0) {
+                guest.update(input(1, frame));
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(host.is_connected() && guest.is_connected());
+        assert!(mispredicted > 0);
+        assert_eq!(host.desynced(), None);
+        assert_eq!(guest.desynced(), None);
+        let shared: Vec<_> = host
+            .checksums
+            .iter()
+            .filter_map(|local| {
+                let remote = guest.checksums.iter().find(|el| el.0 == local.0)?;
+                Some((local, remote))
+            })
+            .collect();
+        assert!(!shared.is_empty());
+        for (local, remote) in shared {
+            assert_eq!(local.1, remote.1, "checksums differ at tick {}", local.0);
+        }
+    }
+}
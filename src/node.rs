@@ -16,8 +16,12 @@ Cheese Adventure a small and simple game
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 use raylib::prelude::*;
+use crate::animation::Animator;
+use crate::assets::Sprite;
+use crate::clock::GameClock;
+use crate::font::{self, Align};
+use crate::pointer::Pointer;
 use std::ops::Deref;
-use std::time::Instant;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Direction {
@@ -27,89 +31,47 @@ pub enum Direction {
     RIGHT = 3,
 }
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum Gamestate {
     Play,
     GameOver,
     NextLevel,
-    MainMenu,
-    OptionMenu,
 }
 
-pub struct ResourceManager {
-    pub mouse: Texture2D,
-    pub cheese: Texture2D,
-    pub spider: Texture2D,
-    pub exit: Texture2D,
-    pub title: Texture2D,
-    pub start: Texture2D,
-    pub options: Texture2D,
-    pub final_score: Texture2D,
-    pub ceasy: Texture2D,
-    pub retry: Texture2D,
-    pub back: Texture2D,
-    pub volume_text: Texture2D,
-    pub theme : Sound,
-    pub clicksound : Sound,
-    pub pickupsound : Sound,
+//what happened during a step, the scenes turn these into sounds
+#[derive(Copy, Clone, PartialEq)]
+pub enum GameEvent {
+    LevelStarted,
+    CheesePicked(Vector2),
+    ExitOpened(Vector2),
+    PlayerCaught(Vector2),
+    SpiderWoke(Vector2),
+    SpiderStep(Vector2),
+    PowerUp,
 }
 
-impl ResourceManager {
-    pub fn new(handle: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mouse = Self::load_texture(handle, thread, "mouse");
-        let cheese = Self::load_texture(handle, thread, "cheese");
-        let spider = Self::load_texture(handle, thread, "spider");
-        let exit = Self::load_texture(handle, thread, "exit");
-        let title = Self::load_texture(handle, thread, "title");
-        let start = Self::load_texture(handle, thread, "start");
-        let options = Self::load_texture(handle, thread, "options");
-        let final_score = Self::load_texture(handle, thread, "finalscore");
-        let ceasy = Self::load_texture(handle, thread, "cheasy");
-        let retry = Self::load_texture(handle, thread, "retry");
-        let back = Self::load_texture(handle, thread, "back");
-        let volume_text = Self::load_texture(handle, thread, "volume_text");
-        let theme = Self::load_audio("theme");
-        let clicksound = Self::load_audio("click");
-        let pickupsound = Self::load_audio("pickup");
-        
-        Self {
-            mouse,
-            cheese,
-            spider,
-            exit,
-            title,
-            start,
-            options,
-            final_score,
-            ceasy,
-            retry,
-            back,
-            volume_text,
-            theme,
-            clicksound,
-            pickupsound,
-        }
-    }
-
-    fn load_texture(handle: &mut RaylibHandle, thread: &RaylibThread, name: &str) -> Texture2D {
-        handle
-            .load_texture(thread, &format!("textures/{}.png", name))
-            .expect(&format!("missing {} sprite", name))
-    }
-
-    fn load_font(handle: &mut RaylibHandle, thread: &RaylibThread, name: &str) -> Font {
-        handle
-            .load_font(thread, &format!("fonts/{}.ttf", name))
-            .expect(&format!("missing {} font", name))
-    }
+#[derive(Copy, Clone, PartialEq)]
+pub enum GameMode {
+    Classic,
+    TimeAttack,
+    Daily,
+    Coop,
+    Versus,
+}
 
-    fn load_audio(name: &str) -> Sound {
-        Sound::load_sound(&format!("audio/{}.ogg", name)).expect(&format!("missing {} audio file", name))
-    }
-    fn load_music(thread: &RaylibThread, name: &str) -> Music {
-        Music::load_music_stream(thread, &format!("audio/{}.ogg", name)).expect(&format!("missing {} audio file", name))
+impl GameMode {
+    pub fn key(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::TimeAttack => "timeattack",
+            GameMode::Daily => "daily",
+            GameMode::Coop => "coop",
+            GameMode::Versus => "versus",
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct VisibleNode {
     pub pos: Vector2,
     pub size: Vector2,
@@ -124,12 +86,13 @@ impl VisibleNode {
             available: true,
         }
     }
-    pub fn draw(
+    //source is the part of the texture to draw, it is drawn as big as it is
+    pub fn draw_source(
         &self,
-        frame_x: f32,
-        frame_y: f32,
+        source: Rectangle,
         drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
         texture: &Texture2D,
+        tint: Color,
     ) {
         if (!self.available) {
             return;
@@ -137,20 +100,20 @@ impl VisibleNode {
         drawer.draw_texture_pro(
             texture,
             Rectangle {
-                x: frame_x.round(),
-                y: frame_y.round(),
-                width: self.size.x,
-                height: self.size.y,
+                x: source.x.round(),
+                y: source.y.round(),
+                width: source.width,
+                height: source.height,
             },
             Rectangle {
                 x: self.pos.x.round(),
                 y: self.pos.y.round(),
-                width: self.size.x,
-                height: self.size.y,
+                width: source.width,
+                height: source.height,
             },
             Vector2 { x: 0.0, y: 0.0 },
             0.0,
-            Color::WHITE,
+            tint,
         );
     }
     pub fn collision(&self, el_x: f32, el_y: f32, el_w: f32, el_h: f32) -> bool {
@@ -179,38 +142,41 @@ impl VisibleNode {
     }
 }
 
+#[derive(Clone)]
 pub struct AnimatedNode {
     pub node: VisibleNode,
-    pub animation_steps_count: u8,
-    pub animation_step: u8,
-    pub animation_speed: u32,
-    pub last_time_point: Instant,
+    pub animator: Animator,
 }
 
 impl AnimatedNode {
-    pub fn create_an(
-        x: f32,
-        y: f32,
-        w: f32,
-        h: f32,
-        animation_steps_count: u8,
-        animation_speed: u32,
-    ) -> Self {
-        let last_time_point = Instant::now();
-        let animation_step = 0;
+    pub fn create_an(x: f32, y: f32, w: f32, h: f32, clip: &str) -> Self {
         Self {
             node: VisibleNode::create_vn(x, y, w, h),
-            animation_steps_count,
-            animation_step,
-            animation_speed,
-            last_time_point,
+            animator: Animator::new(clip),
         }
     }
-    pub fn cycle_animation(&mut self) {
-        if (self.last_time_point.elapsed().as_millis() > self.animation_speed as u128) {
-            self.animation_step = (self.animation_step + 1) % self.animation_steps_count;
-            self.last_time_point = Instant::now();
+    //plays clip and draws its current frame, a sprite without the clip is drawn whole from the corner,
+    //the event of a frame that just came up is pushed to events with the node position
+    pub fn draw_clip(
+        &mut self,
+        clip: &str,
+        drawer: &mut RaylibTextureMode<RaylibDrawHandle>,
+        sprite: &Sprite,
+        clock: &GameClock,
+        tint: Color,
+        events: &mut Vec<(String, Vector2)>,
+    ) {
+        self.animator.play(clip);
+        if let Some(event) = self.animator.update(clock, &sprite.clips) {
+            events.push((event, self.node.pos.clone()));
         }
+        let source = self.animator.source(&sprite.clips).unwrap_or(Rectangle {
+            x: 0.0,
+            y: 0.0,
+            width: self.node.size.x,
+            height: self.node.size.y,
+        });
+        self.node.draw_source(source, drawer, &sprite.texture, tint);
     }
 }
 
@@ -227,8 +193,9 @@ pub struct AnimatedButton {
     animation_duration: u32,
     animation_speed: u32,
     animation_status : bool,
-    start : Instant,
-    delta : Instant,
+    idle_color : Color,
+    start : u64,
+    delta : u64,
     pressed : bool,
     ready: bool,
 }
@@ -241,25 +208,49 @@ impl AnimatedButton {
             animation_duration: 1000,
             animation_speed: 100,
             animation_status : true,
-            start : Instant::now(),
-            delta : Instant::now(),
+            idle_color : Color{r:0,g:167,b:255,a:255},
+            start : 0,
+            delta : 0,
             pressed: false,
             ready: false,
         }
     }
-    fn cycle_animation(&mut self){
-        if(self.start.elapsed().as_millis() >= self.animation_duration as u128){
+    pub fn with_color(mut self, idle_color : Color) -> Self{
+        self.idle_color = idle_color;
+        self
+    }
+    pub fn set_color(&mut self, idle_color : Color){
+        self.idle_color = idle_color;
+    }
+    pub fn get_pos(&self) -> &Vector2{
+        return &self.pos;
+    }
+    pub fn get_size(&self) -> &Vector2{
+        return &self.size;
+    }
+    pub fn contains(&self, point : Vector2) -> bool{
+        point.x >= self.pos.x && point.x < self.pos.x + self.size.x
+            && point.y >= self.pos.y && point.y < self.pos.y + self.size.y
+    }
+    pub fn is_hovered(&self, pointer : &Pointer) -> bool{
+        pointer.position().map_or(false, |el| self.contains(el))
+    }
+    pub fn is_clicked(&self, pointer : &Pointer) -> bool{
+        pointer.is_released() && self.is_hovered(pointer)
+    }
+    fn cycle_animation(&mut self, clock : &GameClock){
+        if(clock.elapsed_since(self.start) >= self.animation_duration as u64){
             self.ready = true;
             return;
         }
-        if(self.delta.elapsed().as_millis() >=self.animation_speed as u128){
+        if(clock.elapsed_since(self.delta) >= self.animation_speed as u64){
             self.animation_status = !self.animation_status;
-            self.delta = Instant::now();
+            self.delta = clock.now();
         }
     }
-    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, texture : &Texture2D){
+    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, texture : &Texture2D, clock : &GameClock){
         if(self.pressed && !self.ready){
-            self.cycle_animation();
+            self.cycle_animation(clock);
         }
         drawer.draw_texture_pro(
             texture,
@@ -277,11 +268,29 @@ impl AnimatedButton {
             },
             Vector2 { x: 0.0, y: 0.0 },
             0.0,
-            if(self.animation_status){Color{r:0,g:167,b:255,a:255}}else{Color{r:255,g:103,b:0,a:255}},
+            if(self.animation_status){self.idle_color}else{Color{r:255,g:103,b:0,a:255}},
+        );
+    }
+    //same as draw but the button is a filled box with the text cut out of it
+    pub fn draw_label(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, text : &str, clock : &GameClock){
+        if(self.pressed && !self.ready){
+            self.cycle_animation(clock);
+        }
+        let color = if(self.animation_status){self.idle_color}else{Color{r:255,g:103,b:0,a:255}};
+        drawer.draw_rectangle(self.pos.x as i32, self.pos.y as i32, self.size.x as i32, self.size.y as i32, color);
+        font::draw_text(
+            drawer,
+            text,
+            Vector2 {
+                x: self.pos.x + self.size.x / 2.0,
+                y: self.pos.y + ((self.size.y + font::GLYPH_HEIGHT) / 2.0).floor() - 1.0,
+            },
+            Align::Center,
+            Color{r:70,g:83,b:98,a:255},
         );
     }
-    pub fn click(&mut self){
-        self.start = Instant::now();
+    pub fn click(&mut self, clock : &GameClock){
+        self.start = clock.now();
         self.pressed = true;
     }
     pub fn reset(&mut self){
@@ -303,54 +312,66 @@ pub struct NumberDisplay{
     pos : Vector2,
     pub value : u32,
     backup : u32,
-    start : Instant,
+    start : u64,
+    color : Color,
 }
 
 impl NumberDisplay{
     pub fn new(x : f32, y : f32) -> Self{
-        Self{pos:Vector2 { x, y}, value:0, backup:0, start: Instant::now()}
+        Self{pos:Vector2 { x, y}, value:0, backup:0, start: 0, color: Color{r:255,g:103,b:0,a:255}}
     }
-    fn cycle_animation(&mut self){
-        if(self.backup != self.value && self.start.elapsed().as_millis() > 50){
+    fn cycle_animation(&mut self, clock : &GameClock){
+        if(self.backup != self.value && clock.elapsed_since(self.start) > 50){
             self.value += 1;
-            self.start = Instant::now();
+            self.start = clock.now();
         }
     }
-    pub fn escalate(&mut self){
+    pub fn escalate(&mut self, clock : &GameClock){
         self.backup = self.value;
         self.value = 0;
-        self.start = Instant::now();
+        self.start = clock.now();
     }
     pub fn has_escalated(&self) -> bool{
         return self.value == self.backup;
     }
-    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>){
-        let text = self.value.to_string();
-        let mut truepos = self.pos;
+    pub fn draw(&mut self, drawer: &mut RaylibTextureMode<RaylibDrawHandle>, clock : &GameClock){
         if(self.backup != 0){
-            self.cycle_animation();
+            self.cycle_animation(clock);
         }
+        NumberDisplay::draw_digits(drawer, &self.value.to_string(), self.pos, self.color);
+    }
+    //7-segment renderer, every digit is 4x7 pixels plus 1 pixel of spacing, dots and colons take 2 pixels
+    pub fn draw_digits(drawer: &mut RaylibTextureMode<RaylibDrawHandle>, text : &str, pos : Vector2, color : Color){
+        let mut truepos = pos;
         for digit in text.chars(){
+            if(digit == '.' || digit == ':'){
+                drawer.draw_rectangle(truepos.x as i32, truepos.y as i32 - 6, 1, 1, color);
+                if(digit == ':'){
+                    drawer.draw_rectangle(truepos.x as i32, truepos.y as i32 - 2, 1, 1, color);
+                }
+                truepos.x += 2.0;
+                continue;
+            }
             if(digit != '1' && digit != '4'){
-                drawer.draw_rectangle(truepos.x as i32 +1, truepos.y as i32, 2, 1, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32 +1, truepos.y as i32, 2, 1, color);
             }
             if(digit != '5' && digit != '6'){
-                drawer.draw_rectangle(truepos.x as i32 +3, truepos.y as i32 - 2, 1, 2, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32 +3, truepos.y as i32 - 2, 1, 2, color);
             }
             if(digit != '2'){
-                drawer.draw_rectangle(truepos.x as i32 +3, truepos.y as i32 - 5, 1, 2, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32 +3, truepos.y as i32 - 5, 1, 2, color);
             }
             if(digit != '1' && digit != '4' && digit != '7'){
-                drawer.draw_rectangle(truepos.x as i32 +1, truepos.y as i32 - 6, 2, 1, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32 +1, truepos.y as i32 - 6, 2, 1, color);
             }
             if(digit == '0' || digit == '2' || digit == '6' || digit == '8'){
-                drawer.draw_rectangle(truepos.x as i32, truepos.y as i32 - 5, 1, 2, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32, truepos.y as i32 - 5, 1, 2, color);
             }
             if(digit != '1' && digit != '2' && digit != '3' && digit != '7'){
-                drawer.draw_rectangle(truepos.x as i32, truepos.y as i32 - 2, 1, 2, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32, truepos.y as i32 - 2, 1, 2, color);
             }
             if(digit != '0' && digit != '1' && digit != '7'){
-                drawer.draw_rectangle(truepos.x as i32 +1, truepos.y as i32 - 3, 2, 1, Color{r:255,g:103,b:0,a:255});
+                drawer.draw_rectangle(truepos.x as i32 +1, truepos.y as i32 - 3, 2, 1, color);
             }
             truepos.x += 5.0;
         }
@@ -366,4 +387,57 @@ impl NumberDisplay{
     pub fn get_pos(&self) -> &Vector2{
         return &self.pos;
     }
+    pub fn set_color(&mut self, color : Color){
+        self.color = color;
+    }
+    pub fn width(&self) -> f32{
+        return NumberDisplay::text_width(&self.value.to_string());
+    }
+    pub fn text_width(text : &str) -> f32{
+        return text.chars().map(|el| if(el == '.' || el == ':'){2.0}else{5.0}).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_display_counts_up_with_the_game_clock() {
+        let mut clock = GameClock::manual();
+        let mut display = NumberDisplay::new(0.0, 0.0);
+        display.value = 3;
+        display.escalate(&clock);
+        assert_eq!(display.value, 0);
+        clock.advance(51);
+        display.cycle_animation(&clock);
+        assert_eq!(display.value, 1);
+        //nothing moves while the clock is paused
+        clock.pause();
+        clock.advance(500);
+        display.cycle_animation(&clock);
+        assert_eq!(display.value, 1);
+        //at double speed 26ms are enough for the next step
+        clock.resume();
+        clock.set_time_scale(2.0);
+        clock.advance(26);
+        display.cycle_animation(&clock);
+        assert_eq!(display.value, 2);
+        clock.advance(26);
+        display.cycle_animation(&clock);
+        assert!(display.has_escalated());
+    }
+
+    #[test]
+    fn animated_nodes_keep_their_clip_playing() {
+        let (mut clips, _) = crate::animation::parse_clips("cheese idle loop 0,0,5,5,100 right=2");
+        let clips = clips.remove("cheese").unwrap();
+        let mut clock = GameClock::manual();
+        let mut node = AnimatedNode::create_an(0.0, 0.0, 5.0, 5.0, "idle");
+        node.animator.update(&clock, &clips);
+        clock.advance(100);
+        node.animator.play("idle");
+        node.animator.update(&clock, &clips);
+        assert_eq!(node.animator.source(&clips).unwrap().x, 5.0);
+    }
 }
@@ -0,0 +1,159 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use raylib::prelude::*;
+use MouseButton::*;
+
+const GAME_SIZE: f32 = 64.0;
+
+//where the 64x64 screen is drawn inside the window, centered with black bars around it
+#[derive(Copy, Clone)]
+pub struct Viewport {
+    x: f32,
+    y: f32,
+    scale: f32,
+}
+
+impl Viewport {
+    pub fn fit(width: i32, height: i32) -> Self {
+        let fit = (width.min(height) as f32 / GAME_SIZE).max(0.0);
+        //whole pixel scales keep the pixels square, smaller windows fall back to a fractional one
+        let scale = if (fit >= 1.0) { fit.floor() } else { fit };
+        Self {
+            x: ((width as f32 - GAME_SIZE * scale) / 2.0).floor(),
+            y: ((height as f32 - GAME_SIZE * scale) / 2.0).floor(),
+            scale,
+        }
+    }
+    pub fn dest(&self) -> Rectangle {
+        Rectangle {
+            x: self.x,
+            y: self.y,
+            width: GAME_SIZE * self.scale,
+            height: GAME_SIZE * self.scale,
+        }
+    }
+    //window coordinates to game coordinates, where y grows upwards; None outside of the game screen,
+    //checked before the flip so the top row of pixels is still inside at y 64
+    pub fn to_game(&self, window: Vector2) -> Option<Vector2> {
+        if (self.scale <= 0.0) {
+            return None;
+        }
+        let x = (window.x - self.x) / self.scale;
+        let from_top = (window.y - self.y) / self.scale;
+        if (x < 0.0 || from_top < 0.0 || x >= GAME_SIZE || from_top >= GAME_SIZE) {
+            return None;
+        }
+        Some(Vector2 { x, y: GAME_SIZE - from_top })
+    }
+}
+
+//the OS pointer as seen from the game screen, refreshed once per frame
+pub struct Pointer {
+    position: Option<Vector2>,
+    moved: bool,
+    pressed: bool,
+    down: bool,
+    released: bool,
+}
+
+impl Pointer {
+    pub fn new() -> Self {
+        Self {
+            position: None,
+            moved: false,
+            pressed: false,
+            down: false,
+            released: false,
+        }
+    }
+    pub fn update(&mut self, handle: &RaylibHandle, viewport: &Viewport) {
+        let position = viewport.to_game(handle.get_mouse_position());
+        self.moved = match (position, self.position) {
+            (Some(now), Some(before)) => {
+                now.x as i32 != before.x as i32 || now.y as i32 != before.y as i32
+            }
+            (now, before) => now.is_some() != before.is_some(),
+        };
+        self.position = position;
+        self.pressed = handle.is_mouse_button_pressed(MOUSE_LEFT_BUTTON);
+        self.down = handle.is_mouse_button_down(MOUSE_LEFT_BUTTON);
+        self.released = handle.is_mouse_button_released(MOUSE_LEFT_BUTTON);
+    }
+    pub fn position(&self) -> Option<Vector2> {
+        self.position
+    }
+    pub fn has_moved(&self) -> bool {
+        self.moved
+    }
+    pub fn is_pressed(&self) -> bool {
+        self.pressed && self.position.is_some()
+    }
+    pub fn is_down(&self) -> bool {
+        self.down && self.position.is_some()
+    }
+    pub fn is_released(&self) -> bool {
+        self.released && self.position.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_map_to_the_game_screen() {
+        //window size, point in the window, point in the game
+        let cases: [((i32, i32), (f32, f32), Option<(f32, f32)>); 16] = [
+            //a square window, scaled 10 times
+            ((640, 640), (0.0, 0.0), Some((0.0, 64.0))),
+            ((640, 640), (320.0, 320.0), Some((32.0, 32.0))),
+            ((640, 640), (639.0, 639.0), Some((63.9, 0.1))),
+            ((640, 640), (640.0, 320.0), None),
+            ((640, 640), (320.0, 640.0), None),
+            //a wide window, bars on the left and on the right
+            ((800, 640), (40.0, 320.0), None),
+            ((800, 640), (80.0, 0.0), Some((0.0, 64.0))),
+            ((800, 640), (719.0, 320.0), Some((63.9, 32.0))),
+            ((800, 640), (760.0, 320.0), None),
+            //a tall window, bars above and below
+            ((640, 800), (320.0, 40.0), None),
+            ((640, 800), (320.0, 80.0), Some((32.0, 64.0))),
+            ((640, 800), (320.0, 719.0), Some((32.0, 0.1))),
+            ((640, 800), (320.0, 720.0), None),
+            //the scale is rounded down to whole pixels, the rest becomes bars
+            ((100, 100), (17.0, 50.0), None),
+            //below 64 pixels the scale is fractional
+            ((32, 32), (16.0, 16.0), Some((32.0, 32.0))),
+            ((0, 0), (0.0, 0.0), None),
+        ];
+        for ((width, height), (x, y), expected) in cases {
+            let game = Viewport::fit(width, height).to_game(Vector2 { x, y });
+            let matches = match (game, expected) {
+                (Some(game), Some((x, y))) => {
+                    (game.x - x).abs() < 0.001 && (game.y - y).abs() < 0.001
+                }
+                (game, expected) => game.is_none() && expected.is_none(),
+            };
+            assert!(
+                matches,
+                "{}x{} ({}, {}) gave {:?}",
+                width, height, x, y, game
+            );
+        }
+    }
+}
@@ -0,0 +1,268 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::assets::ResourceManager;
+use crate::audio::Audio;
+use crate::clock::GameClock;
+use crate::effect::{Effect, ScreenEffect};
+use crate::input::Controls;
+use crate::lang::Strings;
+use crate::music::MusicPlayer;
+use crate::node::GameEvent;
+use crate::pointer::Pointer;
+use crate::score::{DailyHistory, HighScores, VersusScore};
+use crate::sfx::Sfx;
+use raylib::prelude::*;
+
+//everything the scenes share and that outlives any of them
+pub struct Context {
+    pub resources: ResourceManager,
+    pub clock: GameClock,
+    pub ui_clock: GameClock,
+    pub controls: [Controls; 2],
+    pub pointer: Pointer,
+    pub music: MusicPlayer,
+    pub sfx: Sfx,
+    pub strings: Strings,
+    pub high_scores: HighScores,
+    pub daily_history: DailyHistory,
+    pub versus_score: VersusScore,
+}
+
+impl Context {
+    pub fn play_sound(&mut self, audio: &mut dyn Audio, id: &str) {
+        self.sfx
+            .play(self.resources.sounds(), audio, &self.ui_clock, id);
+    }
+    pub fn play_events(
+        &mut self,
+        audio: &mut dyn Audio,
+        events: &[GameEvent],
+        listeners: &[Vector2],
+    ) {
+        self.sfx.play_events(
+            self.resources.sounds(),
+            audio,
+            &self.ui_clock,
+            events,
+            listeners,
+        );
+    }
+    pub fn play_frame_events(
+        &mut self,
+        audio: &mut dyn Audio,
+        events: &[(String, Vector2)],
+        listeners: &[Vector2],
+    ) {
+        self.sfx.play_frame_events(
+            self.resources.sounds(),
+            audio,
+            &self.ui_clock,
+            events,
+            listeners,
+        );
+    }
+}
+
+//what a popped scene asks of the one below it
+#[derive(Copy, Clone, PartialEq)]
+pub enum Signal {
+    Back,
+    Restart,
+}
+
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),
+    Pop(Signal),
+    Replace(Box<dyn Scene>),
+    //drops the whole stack and starts over from this scene
+    Reset(Box<dyn Scene>),
+    Quit,
+}
+
+pub trait Scene {
+    fn enter(&mut self, _ctx: &mut Context) {}
+    fn exit(&mut self, _ctx: &mut Context) {}
+    //called when the scene above this one has been popped
+    fn resume(&mut self, _ctx: &mut Context, _signal: Signal) {}
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition;
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>);
+    //overlays are drawn over the scene below instead of hiding it
+    fn is_overlay(&self) -> bool {
+        false
+    }
+    //effect played when this scene is entered or left, None cuts instantly
+    fn effect(&self) -> Option<Effect> {
+        Some(Effect::Fade)
+    }
+}
+
+//only the top scene is updated, it is drawn together with the overlays stacked on it
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    quit: bool,
+    pending: Option<Transition>,
+    effect: Option<ScreenEffect>,
+}
+
+impl SceneStack {
+    pub fn new(ctx: &mut Context, mut first: Box<dyn Scene>) -> Self {
+        first.enter(ctx);
+        Self {
+            scenes: vec![first],
+            quit: false,
+            pending: None,
+            effect: None,
+        }
+    }
+    pub fn update(&mut self, ctx: &mut Context, handle: &mut RaylibHandle, audio: &mut dyn Audio) {
+        //the scenes get no input while an effect is playing
+        if let Some(effect) = &mut self.effect {
+            if (effect.should_swap(&ctx.ui_clock)) {
+                if let Some(transition) = self.pending.take() {
+                    self.apply(ctx, transition);
+                }
+            }
+            if (self
+                .effect
+                .as_ref()
+                .map_or(true, |el| el.is_done(&ctx.ui_clock)))
+            {
+                self.effect = None;
+            }
+            return;
+        }
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(ctx, handle, audio),
+            None => Transition::Quit,
+        };
+        match self.effect_for(&transition) {
+            Some(effect) => {
+                self.effect = Some(ScreenEffect::new(effect, &ctx.ui_clock));
+                self.pending = Some(transition);
+            }
+            None => self.apply(ctx, transition),
+        }
+    }
+    fn effect_for(&self, transition: &Transition) -> Option<Effect> {
+        match transition {
+            Transition::Push(scene) | Transition::Replace(scene) | Transition::Reset(scene) => {
+                scene.effect()
+            }
+            Transition::Pop(_) => self.scenes.last().and_then(|el| el.effect()),
+            Transition::None | Transition::Quit => None,
+        }
+    }
+    pub fn apply(&mut self, ctx: &mut Context, transition: Transition) {
+        match transition {
+            Transition::None => (),
+            Transition::Push(mut scene) => {
+                scene.enter(ctx);
+                self.scenes.push(scene);
+            }
+            Transition::Pop(signal) => {
+                if let Some(mut scene) = self.scenes.pop() {
+                    scene.exit(ctx);
+                }
+                match self.scenes.last_mut() {
+                    Some(scene) => scene.resume(ctx, signal),
+                    None => self.quit = true,
+                }
+            }
+            Transition::Replace(mut scene) => {
+                if let Some(mut old) = self.scenes.pop() {
+                    old.exit(ctx);
+                }
+                scene.enter(ctx);
+                self.scenes.push(scene);
+            }
+            Transition::Reset(mut scene) => {
+                while let Some(mut old) = self.scenes.pop() {
+                    old.exit(ctx);
+                }
+                scene.enter(ctx);
+                self.scenes.push(scene);
+            }
+            Transition::Quit => self.quit = true,
+        }
+    }
+    pub fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        let first = self
+            .scenes
+            .iter()
+            .rposition(|el| !el.is_overlay())
+            .unwrap_or(0);
+        for scene in &mut self.scenes[first..] {
+            scene.draw(ctx, drawer);
+        }
+        if let Some(effect) = &self.effect {
+            effect.draw(drawer, &ctx.ui_clock);
+        }
+    }
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::LANG_DIR;
+    use crate::node::GameMode;
+    use crate::scenes::{MenuScene, PauseScene, PlayScene};
+    use std::env;
+
+    fn headless_context() -> Context {
+        let scores = env::temp_dir().join("cheese_adventure_scene_scores.txt");
+        let daily = env::temp_dir().join("cheese_adventure_scene_daily.txt");
+        Context {
+            resources: ResourceManager::headless(),
+            clock: GameClock::new(),
+            ui_clock: GameClock::new(),
+            controls: [Controls::wasd(), Controls::arrows()],
+            pointer: Pointer::new(),
+            music: MusicPlayer::new(),
+            sfx: Sfx::new(),
+            strings: Strings::load(LANG_DIR, "en"),
+            high_scores: HighScores::load(&scores.to_string_lossy()),
+            daily_history: DailyHistory::load(&daily.to_string_lossy()),
+            versus_score: VersusScore::new(),
+        }
+    }
+
+    #[test]
+    fn quitting_a_paused_level_restarts_the_clock() {
+        let mut ctx = headless_context();
+        let mut stack = SceneStack::new(&mut ctx, Box::new(MenuScene::new()));
+        stack.apply(
+            &mut ctx,
+            Transition::Push(Box::new(PlayScene::new(GameMode::Classic))),
+        );
+        //what escape does before it pushes the pause menu
+        ctx.clock.pause();
+        stack.apply(&mut ctx, Transition::Push(Box::new(PauseScene::new(true))));
+        //the quit entry of the pause menu
+        stack.apply(&mut ctx, Transition::Reset(Box::new(MenuScene::new())));
+        assert!(!ctx.clock.is_paused());
+    }
+}
@@ -0,0 +1,118 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::assets::AssetError;
+use crate::audio::Audio;
+use crate::effect::Effect;
+use crate::font::{self, Align};
+use crate::lang::key;
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::scene::{Context, Scene, Signal, Transition};
+use raylib::prelude::*;
+use std::path::Path;
+use KeyboardKey::*;
+
+const MAX_ROWS: usize = 6;
+
+#[derive(Copy, Clone, PartialEq)]
+enum AssetErrorsAction {
+    Continue,
+}
+
+//pushed over the main menu when some assets were replaced by the fallbacks
+pub struct AssetErrorsScene {
+    menu: Menu<AssetErrorsAction>,
+}
+
+impl AssetErrorsScene {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(vec![MenuEntry::button(
+                AssetErrorsAction::Continue,
+                12.0,
+                3.0,
+                40.0,
+                7.0,
+                key::ERRORS_CONTINUE,
+            )
+            .with_key(KEY_A)]),
+        }
+    }
+}
+
+impl Scene for AssetErrorsScene {
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(AssetErrorsAction::Continue)) | Some(MenuEvent::Back) => {
+                Transition::Pop(Signal::Back)
+            }
+            _ => Transition::None,
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        drawer.draw_rectangle(0, 0, 64, 64, Color { r: 0, g: 0, b: 0, a: 220 });
+        font::draw_text(
+            drawer,
+            ctx.strings.get(key::ERRORS_TITLE),
+            Vector2 { x: 32.0, y: 60.0 },
+            Align::Center,
+            Color::WHITE,
+        );
+        //only the file names fit, the full reasons are printed on stderr
+        let errors = ctx.resources.errors();
+        let mut row_y = 52.0;
+        for error in errors.iter().take(MAX_ROWS) {
+            let name = Path::new(error.path())
+                .file_name()
+                .map(|el| el.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let color = match error {
+                AssetError::Missing { .. } => Color { r: 255, g: 103, b: 0, a: 255 },
+                AssetError::Invalid { .. } | AssetError::Undeclared { .. } => Color::RED,
+            };
+            font::draw_text(
+                drawer,
+                &name,
+                Vector2 { x: 2.0, y: row_y },
+                Align::Left,
+                color,
+            );
+            row_y -= 7.0;
+        }
+        if (errors.len() > MAX_ROWS) {
+            font::draw_text(
+                drawer,
+                &format!("+{}", errors.len() - MAX_ROWS),
+                Vector2 { x: 62.0, y: row_y + 7.0 },
+                Align::Right,
+                Color::WHITE,
+            );
+        }
+        self.menu.draw(ctx, drawer);
+    }
+    fn is_overlay(&self) -> bool {
+        true
+    }
+    fn effect(&self) -> Option<Effect> {
+        None
+    }
+}
@@ -0,0 +1,120 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::effect::Effect;
+use crate::font::{self, Align};
+use crate::lang::key;
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::node::{GameMode, NumberDisplay};
+use crate::scene::{Context, Scene, Transition};
+use crate::scenes::PlayScene;
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+#[derive(Copy, Clone, PartialEq)]
+enum GameOverAction {
+    Retry,
+}
+
+pub struct GameOverScene {
+    mode: GameMode,
+    daily_date: String,
+    total_points: NumberDisplay,
+    best_score: NumberDisplay,
+    menu: Menu<GameOverAction>,
+}
+
+impl GameOverScene {
+    pub fn new(mode: GameMode, daily_date: String, score: u32) -> Self {
+        let mut total_points = NumberDisplay::new(20.0, 45.0);
+        total_points.value = score;
+        let mut best_score = NumberDisplay::new(20.0, 12.0);
+        best_score.set_color(Color { r: 0, g: 167, b: 255, a: 255 });
+        Self {
+            mode,
+            daily_date,
+            total_points,
+            best_score,
+            //retry only unlocks once the score has finished counting up
+            menu: Menu::new(vec![MenuEntry::button(
+                GameOverAction::Retry,
+                5.0,
+                20.0,
+                30.0,
+                7.0,
+                key::GAMEOVER_RETRY,
+            )
+            .with_key(KEY_A)
+            .disabled()]),
+        }
+    }
+}
+
+impl Scene for GameOverScene {
+    fn enter(&mut self, ctx: &mut Context) {
+        let score = self.total_points.value;
+        //the daily attempt that counts was saved by the play scene
+        if (self.mode == GameMode::Daily) {
+            self.best_score.value = ctx
+                .daily_history
+                .score_of(&self.daily_date)
+                .unwrap_or(score);
+        } else {
+            ctx.high_scores.submit(self.mode.key(), score);
+            self.best_score.value = ctx.high_scores.best(self.mode.key());
+        }
+        self.total_points.escalate(&ctx.ui_clock);
+    }
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        self.menu
+            .set_enabled(GameOverAction::Retry, self.total_points.has_escalated());
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(GameOverAction::Retry)) => {
+                Transition::Replace(Box::new(PlayScene::new(self.mode)))
+            }
+            _ => Transition::None,
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        font::draw_text(
+            drawer,
+            ctx.strings.get(key::GAMEOVER_TITLE),
+            Vector2 { x: 32.0, y: 60.0 },
+            Align::Center,
+            Color::WHITE,
+        );
+        font::draw_text(
+            drawer,
+            ctx.strings.get(key::GAMEOVER_BEST),
+            Vector2 { x: 20.0, y: 18.0 },
+            Align::Left,
+            Color { r: 0, g: 167, b: 255, a: 255 },
+        );
+        self.total_points.draw(drawer, &ctx.ui_clock);
+        self.best_score.draw(drawer, &ctx.ui_clock);
+        self.menu.draw(ctx, drawer);
+    }
+    fn effect(&self) -> Option<Effect> {
+        Some(Effect::Dissolve)
+    }
+}
@@ -0,0 +1,92 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::lang::key;
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::node::NumberDisplay;
+use crate::scene::{Context, Scene, Signal, Transition};
+use crate::score::today;
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+#[derive(Copy, Clone, PartialEq)]
+enum HistoryAction {
+    Back,
+}
+
+pub struct HistoryScene {
+    menu: Menu<HistoryAction>,
+}
+
+impl HistoryScene {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(vec![MenuEntry::button(
+                HistoryAction::Back,
+                5.0,
+                5.0,
+                34.0,
+                7.0,
+                key::COMMON_BACK,
+            )
+            .with_key(KEY_A)]),
+        }
+    }
+}
+
+impl Scene for HistoryScene {
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(HistoryAction::Back)) | Some(MenuEvent::Back) => {
+                Transition::Pop(Signal::Back)
+            }
+            _ => Transition::None,
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        //one row per day, month and day on the left and the score on the right
+        let today = today();
+        let mut row_y = 62.0;
+        for (date, score) in ctx.daily_history.recent(5) {
+            let color = if (*date == today) {
+                Color { r: 0, g: 167, b: 255, a: 255 }
+            } else {
+                Color { r: 255, g: 103, b: 0, a: 255 }
+            };
+            NumberDisplay::draw_digits(
+                drawer,
+                &date.get(5..).unwrap_or("").replace("-", ""),
+                Vector2 { x: 1.0, y: row_y },
+                color,
+            );
+            NumberDisplay::draw_digits(
+                drawer,
+                &score.to_string(),
+                Vector2 { x: 28.0, y: row_y },
+                color,
+            );
+            row_y -= 10.0;
+        }
+        self.menu.draw(ctx, drawer);
+    }
+}
@@ -0,0 +1,120 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::lang::key;
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::node::GameMode;
+use crate::scene::{Context, Scene, Signal, Transition};
+use crate::scenes::{HistoryScene, LobbyScene, OptionsScene, PlayScene};
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+#[derive(Copy, Clone, PartialEq)]
+enum MainAction {
+    Start,
+    Daily,
+    Options,
+    TimeAttack,
+    Coop,
+    Versus,
+    Online,
+}
+
+fn main_menu() -> Menu<MainAction> {
+    Menu::new(vec![
+        MenuEntry::button(MainAction::Start, 2.0, 24.0, 30.0, 7.0, key::MENU_START).with_key(KEY_A),
+        MenuEntry::button(MainAction::Daily, 33.0, 24.0, 30.0, 7.0, key::MENU_DAILY)
+            .with_color(Color { r: 253, g: 249, b: 0, a: 255 })
+            .with_key(KEY_F),
+        MenuEntry::button(MainAction::Options, 2.0, 16.0, 36.0, 7.0, key::MENU_OPTIONS)
+            .with_key(KEY_S),
+        MenuEntry::button(MainAction::TimeAttack, 2.0, 8.0, 30.0, 7.0, key::MENU_TIME)
+            .with_color(Color { r: 0, g: 228, b: 48, a: 255 })
+            .with_key(KEY_D),
+        MenuEntry::button(MainAction::Coop, 33.0, 8.0, 30.0, 7.0, key::MENU_COOP)
+            .with_color(Color { r: 200, g: 122, b: 255, a: 255 })
+            .with_key(KEY_C),
+        MenuEntry::button(MainAction::Versus, 2.0, 0.0, 30.0, 7.0, key::MENU_VERSUS)
+            .with_color(Color { r: 230, g: 41, b: 55, a: 255 })
+            .with_key(KEY_V),
+        MenuEntry::button(MainAction::Online, 33.0, 0.0, 30.0, 7.0, key::MENU_ONLINE)
+            .with_color(Color { r: 0, g: 228, b: 228, a: 255 })
+            .with_key(KEY_N),
+    ])
+}
+
+pub struct MenuScene {
+    menu: Menu<MainAction>,
+}
+
+impl MenuScene {
+    pub fn new() -> Self {
+        Self { menu: main_menu() }
+    }
+}
+
+impl Scene for MenuScene {
+    fn enter(&mut self, ctx: &mut Context) {
+        ctx.music.play(ctx.resources.music("menu"));
+    }
+    fn resume(&mut self, ctx: &mut Context, _signal: Signal) {
+        ctx.music.play(ctx.resources.music("menu"));
+    }
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        if (handle.is_key_released(KEY_H)) {
+            ctx.play_sound(audio, "click");
+            return Transition::Push(Box::new(HistoryScene::new()));
+        }
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(action)) => match action {
+                MainAction::Start => {
+                    Transition::Replace(Box::new(PlayScene::new(GameMode::Classic)))
+                }
+                MainAction::Daily => Transition::Replace(Box::new(PlayScene::new(GameMode::Daily))),
+                MainAction::TimeAttack => {
+                    Transition::Replace(Box::new(PlayScene::new(GameMode::TimeAttack)))
+                }
+                MainAction::Coop => Transition::Replace(Box::new(PlayScene::new(GameMode::Coop))),
+                MainAction::Versus => {
+                    ctx.versus_score.clear();
+                    Transition::Replace(Box::new(PlayScene::versus(0)))
+                }
+                MainAction::Online => Transition::Push(Box::new(LobbyScene::new())),
+                MainAction::Options => Transition::Push(Box::new(OptionsScene::new())),
+            },
+            Some(MenuEvent::Back) => Transition::Quit,
+            _ => Transition::None,
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        drawer.draw_texture_pro(
+            &ctx.resources[ctx.resources.texture("title")].texture,
+            Rectangle { x: 0.0, y: 0.0, width: 64.0, height: -32.0 },
+            Rectangle { x: 0.0, y: 32.0, width: 64.0, height: 32.0 },
+            Vector2 { x: 0.0, y: 0.0 },
+            0.0,
+            Color::WHITE,
+        );
+        self.menu.draw(ctx, drawer);
+    }
+}
@@ -0,0 +1,36 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+mod asset_errors;
+mod game_over;
+mod history;
+mod menu;
+mod online;
+mod options;
+mod pause;
+mod play;
+mod round_over;
+
+pub use asset_errors::AssetErrorsScene;
+pub use game_over::GameOverScene;
+pub use history::HistoryScene;
+pub use menu::MenuScene;
+pub use online::{LobbyScene, NetPlayScene};
+pub use options::OptionsScene;
+pub use pause::PauseScene;
+pub use play::PlayScene;
+pub use round_over::RoundOverScene;
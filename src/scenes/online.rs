@@ -0,0 +1,292 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::font::{self, Align};
+use crate::lang::key;
+use crate::net::{Lobby, NetMatch};
+use crate::node::{AnimatedButton, GameMode, NumberDisplay};
+use crate::scene::{Context, Scene, Signal, Transition};
+use raylib::prelude::*;
+use std::net::SocketAddr;
+use KeyboardKey::*;
+
+//the safe binding of raylib 3.7 has no way to read the typed characters queue
+fn char_pressed() -> Option<char> {
+    let code = unsafe { ffi::GetCharPressed() };
+    if (code > 0) {
+        char::from_u32(code as u32)
+    } else {
+        None
+    }
+}
+
+pub struct LobbyScene {
+    lobby: Lobby,
+}
+
+impl LobbyScene {
+    pub fn new() -> Self {
+        Self { lobby: Lobby::new() }
+    }
+    fn start(&mut self, net: Option<NetMatch>, loopback_peer: Option<NetMatch>) -> Transition {
+        match net {
+            Some(net) => {
+                self.lobby.error = false;
+                Transition::Push(Box::new(NetPlayScene::new(net, loopback_peer)))
+            }
+            None => {
+                self.lobby.error = true;
+                Transition::None
+            }
+        }
+    }
+}
+
+impl Scene for LobbyScene {
+    fn resume(&mut self, ctx: &mut Context, _signal: Signal) {
+        ctx.music.play(ctx.resources.music("menu"));
+    }
+    fn update(
+        &mut self,
+        _ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        _audio: &mut dyn Audio,
+    ) -> Transition {
+        while let Some(character) = char_pressed() {
+            self.lobby.type_char(character);
+        }
+        if (handle.is_key_pressed(KEY_BACKSPACE)) {
+            self.lobby.erase();
+        }
+        if (handle.is_key_released(KEY_TAB)) {
+            self.lobby.editing_port = !self.lobby.editing_port;
+        }
+        if (handle.is_key_released(KEY_C)) {
+            self.lobby.mode = GameMode::Coop;
+        } else if (handle.is_key_released(KEY_V)) {
+            self.lobby.mode = GameMode::Versus;
+        }
+        if (handle.is_key_released(KEY_Q) || handle.is_key_released(KEY_ESCAPE)) {
+            Transition::Pop(Signal::Back)
+        } else if (handle.is_key_released(KEY_H)) {
+            let net = NetMatch::host(self.lobby.port(), self.lobby.mode).ok();
+            self.start(net, None)
+        } else if (handle.is_key_released(KEY_J)) {
+            let net = self
+                .lobby
+                .target()
+                .and_then(|address| NetMatch::join(address).ok());
+            self.start(net, None)
+        } else if (handle.is_key_released(KEY_L)) {
+            //both peers run in this process and talk through the loopback interface
+            let net = NetMatch::host(self.lobby.port(), self.lobby.mode).ok();
+            let peer = NetMatch::join(SocketAddr::from(([127, 0, 0, 1], self.lobby.port()))).ok();
+            self.start(net, peer)
+        } else {
+            Transition::None
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        //address and port, the field being edited is highlighted
+        let (address_color, port_color) = if (self.lobby.editing_port) {
+            (
+                Color { r: 255, g: 103, b: 0, a: 255 },
+                Color { r: 0, g: 167, b: 255, a: 255 },
+            )
+        } else {
+            (
+                Color { r: 0, g: 167, b: 255, a: 255 },
+                Color { r: 255, g: 103, b: 0, a: 255 },
+            )
+        };
+        NumberDisplay::draw_digits(
+            drawer,
+            &self.lobby.address,
+            Vector2 { x: 1.0, y: 62.0 },
+            address_color,
+        );
+        NumberDisplay::draw_digits(
+            drawer,
+            &self.lobby.port,
+            Vector2 { x: 1.0, y: 50.0 },
+            port_color,
+        );
+        drawer.draw_rectangle(
+            56,
+            44,
+            6,
+            6,
+            if (self.lobby.mode == GameMode::Versus) {
+                Color { r: 230, g: 41, b: 55, a: 255 }
+            } else {
+                Color { r: 200, g: 122, b: 255, a: 255 }
+            },
+        );
+        font::draw_text(
+            drawer,
+            ctx.strings.get(key::LOBBY_HELP),
+            Vector2 { x: 2.0, y: 26.0 },
+            Align::Left,
+            Color { r: 255, g: 255, b: 255, a: 255 },
+        );
+        if (self.lobby.error) {
+            drawer.draw_rectangle_lines(0, 0, 64, 64, Color { r: 230, g: 41, b: 55, a: 255 });
+            font::draw_text(
+                drawer,
+                ctx.strings.get(key::LOBBY_ERROR),
+                Vector2 { x: 32.0, y: 36.0 },
+                Align::Center,
+                Color { r: 230, g: 41, b: 55, a: 255 },
+            );
+        }
+    }
+}
+
+pub struct NetPlayScene {
+    net: NetMatch,
+    loopback_peer: Option<NetMatch>,
+    rematch_button: AnimatedButton,
+}
+
+impl NetPlayScene {
+    pub fn new(net: NetMatch, loopback_peer: Option<NetMatch>) -> Self {
+        Self {
+            net,
+            loopback_peer,
+            rematch_button: AnimatedButton::new(12.0, 5.0, 40.0, 7.0),
+        }
+    }
+}
+
+impl Scene for NetPlayScene {
+    fn enter(&mut self, ctx: &mut Context) {
+        ctx.music.play_layers(
+            ctx.resources.music("play"),
+            ctx.resources.music("play_tense"),
+        );
+    }
+    fn exit(&mut self, _ctx: &mut Context) {
+        self.net.leave();
+        if let Some(peer) = &mut self.loopback_peer {
+            peer.leave();
+        }
+    }
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        if (handle.is_key_released(KEY_ESCAPE)) {
+            return Transition::Pop(Signal::Back);
+        }
+        //taken before the update so a rollback cannot bring back the ones already played
+        let frame_events = self.net.simulation_mut().take_frame_events();
+        self.net.update(ctx.controls[0].read(handle));
+        let events = self.net.simulation_mut().take_events();
+        let sim = self.net.simulation();
+        ctx.play_events(audio, &events, &sim.listeners());
+        ctx.play_frame_events(audio, &frame_events, &sim.listeners());
+        ctx.music.set_mood(sim.danger(), sim.is_cleared());
+        if let Some(peer) = &mut self.loopback_peer {
+            peer.update(ctx.controls[1].read(handle));
+        }
+        let rematch = handle.is_key_released(KEY_A) || self.rematch_button.is_clicked(&ctx.pointer);
+        if (self.net.finished().is_some() && rematch) {
+            ctx.play_sound(audio, "click");
+            self.net.request_rematch();
+            if let Some(peer) = &mut self.loopback_peer {
+                peer.request_rematch();
+            }
+        }
+        //a match out of sync is over, any key that would ask for a rematch leaves it
+        if (self.net.is_disconnected() || (self.net.desynced().is_some() && rematch)) {
+            return Transition::Pop(Signal::Back);
+        }
+        Transition::None
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        if (!self.net.is_connected()) {
+            //blink while waiting for the other player
+            if ((ctx.ui_clock.now() / 500) % 2 == 0) {
+                font::draw_text(
+                    drawer,
+                    ctx.strings.get(key::NET_WAITING),
+                    Vector2 { x: 32.0, y: 34.0 },
+                    Align::Center,
+                    Color { r: 0, g: 228, b: 228, a: 255 },
+                );
+            }
+        } else {
+            self.net
+                .simulation_mut()
+                .draw(drawer, &ctx.resources, &ctx.clock);
+            let sim = self.net.simulation();
+            NumberDisplay::draw_digits(
+                drawer,
+                &sim.cheese_collected().to_string(),
+                Vector2 { x: 1.0, y: 62.0 },
+                Color { r: 255, g: 103, b: 0, a: 255 },
+            );
+            if (self.net.finished().is_some()) {
+                drawer.draw_rectangle(0, 0, 64, 64, Color { r: 0, g: 0, b: 0, a: 170 });
+                //co-op shows the score, versus how long the mouse survived
+                let result = if (self.net.mode() == GameMode::Versus) {
+                    sim.tick() / 60
+                } else {
+                    (sim.score() * sim.level_count()) as u64
+                };
+                NumberDisplay::draw_digits(
+                    drawer,
+                    &result.to_string(),
+                    Vector2 { x: 20.0, y: 45.0 },
+                    Color { r: 255, g: 103, b: 0, a: 255 },
+                );
+                if (!self.net.is_waiting_rematch() || (ctx.ui_clock.now() / 250) % 2 == 0) {
+                    self.rematch_button.draw_label(
+                        drawer,
+                        ctx.strings.get(key::NET_REMATCH),
+                        &ctx.ui_clock,
+                    );
+                }
+            }
+        }
+        if let Some(tick) = self.net.desynced() {
+            drawer.draw_rectangle(0, 0, 64, 64, Color { r: 0, g: 0, b: 0, a: 170 });
+            drawer.draw_rectangle_lines(0, 0, 64, 64, Color { r: 230, g: 41, b: 55, a: 255 });
+            font::draw_text(
+                drawer,
+                ctx.strings.get(key::NET_DESYNC),
+                Vector2 { x: 32.0, y: 40.0 },
+                Align::Center,
+                Color { r: 230, g: 41, b: 55, a: 255 },
+            );
+            NumberDisplay::draw_digits(
+                drawer,
+                &tick.to_string(),
+                Vector2 { x: 20.0, y: 28.0 },
+                Color { r: 255, g: 103, b: 0, a: 255 },
+            );
+            self.rematch_button.draw_label(
+                drawer,
+                ctx.strings.get(key::COMMON_BACK),
+                &ctx.ui_clock,
+            );
+        }
+    }
+}
@@ -0,0 +1,144 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::font::{self, Align};
+use crate::lang::{key, Strings, LANG_DIR};
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::node::NumberDisplay;
+use crate::scene::{Context, Scene, Signal, Transition};
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+#[derive(Copy, Clone, PartialEq)]
+enum OptionAction {
+    Language,
+    Music,
+    Sounds,
+    Fullscreen,
+    Back,
+}
+
+fn options_menu() -> Menu<OptionAction> {
+    Menu::new(vec![
+        MenuEntry::button(
+            OptionAction::Language,
+            2.0,
+            46.0,
+            60.0,
+            7.0,
+            key::LANGUAGE_NAME,
+        )
+        .with_key(KEY_L),
+        MenuEntry::slider(OptionAction::Music, 26.0, 39.0, 20.0, 3.0)
+            .with_label(key::OPTIONS_MUSIC)
+            .with_range(0, 100, 10)
+            .with_slider_keys(KEY_Z, KEY_X),
+        MenuEntry::slider(OptionAction::Sounds, 26.0, 30.0, 20.0, 3.0)
+            .with_label(key::OPTIONS_SOUNDS)
+            .with_range(0, 100, 10)
+            .with_slider_keys(KEY_C, KEY_V),
+        MenuEntry::toggle(OptionAction::Fullscreen, 57.0, 18.0, 5.0)
+            .with_label(key::OPTIONS_FULLSCREEN),
+        MenuEntry::button(OptionAction::Back, 5.0, 5.0, 34.0, 7.0, key::COMMON_BACK)
+            .with_key(KEY_A),
+    ])
+}
+
+fn percent(volume: f32) -> u32 {
+    (volume * 100.0).round() as u32
+}
+
+pub struct OptionsScene {
+    menu: Menu<OptionAction>,
+    music_volume: NumberDisplay,
+    sound_volume: NumberDisplay,
+}
+
+impl OptionsScene {
+    pub fn new() -> Self {
+        Self {
+            menu: options_menu(),
+            music_volume: NumberDisplay::new(48.0, 43.0),
+            sound_volume: NumberDisplay::new(48.0, 34.0),
+        }
+    }
+}
+
+impl Scene for OptionsScene {
+    fn enter(&mut self, ctx: &mut Context) {
+        self.menu
+            .set_value(OptionAction::Music, percent(ctx.music.volume()));
+        self.menu
+            .set_value(OptionAction::Sounds, percent(ctx.resources.sound_volume()));
+    }
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        self.menu
+            .set_toggle(OptionAction::Fullscreen, handle.is_window_fullscreen());
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(OptionAction::Back)) | Some(MenuEvent::Back) => {
+                return Transition::Pop(Signal::Back);
+            }
+            Some(MenuEvent::Activated(OptionAction::Language)) => {
+                //cycles through the shipped tables in alphabetical order
+                let languages = Strings::available(LANG_DIR);
+                let next = languages
+                    .iter()
+                    .position(|el| el == ctx.strings.language())
+                    .map_or(0, |el| (el + 1) % languages.len());
+                if let Some(language) = languages.get(next) {
+                    ctx.strings = Strings::load(LANG_DIR, language);
+                }
+            }
+            Some(MenuEvent::Changed(OptionAction::Music, value)) => {
+                ctx.music.set_volume(value as f32 / 100.0);
+            }
+            Some(MenuEvent::Changed(OptionAction::Sounds, value)) => {
+                ctx.resources.set_sound_volume(value as f32 / 100.0);
+            }
+            Some(MenuEvent::Toggled(OptionAction::Fullscreen, _)) => {
+                handle.toggle_fullscreen();
+            }
+            _ => (),
+        }
+        //right aligned so 100 still fits on the screen
+        self.music_volume.value = percent(ctx.music.volume());
+        self.music_volume
+            .translate(64.0 - self.music_volume.width(), 43.0);
+        self.sound_volume.value = percent(ctx.resources.sound_volume());
+        self.sound_volume
+            .translate(64.0 - self.sound_volume.width(), 34.0);
+        Transition::None
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        font::draw_text(
+            drawer,
+            ctx.strings.get(key::OPTIONS_TITLE),
+            Vector2 { x: 32.0, y: 61.0 },
+            Align::Center,
+            Color::WHITE,
+        );
+        self.menu.draw(ctx, drawer);
+        self.music_volume.draw(drawer, &ctx.ui_clock);
+        self.sound_volume.draw(drawer, &ctx.ui_clock);
+    }
+}
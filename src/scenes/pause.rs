@@ -0,0 +1,110 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::effect::Effect;
+use crate::lang::key;
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::scene::{Context, Scene, Signal, Transition};
+use crate::scenes::{MenuScene, OptionsScene};
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+#[derive(Copy, Clone, PartialEq)]
+enum PauseAction {
+    Resume,
+    Restart,
+    Options,
+    Quit,
+}
+
+//drawn over the paused level, the game clock stays stopped until it is popped
+pub struct PauseScene {
+    menu: Menu<PauseAction>,
+}
+
+impl PauseScene {
+    pub fn new(can_restart: bool) -> Self {
+        let mut menu = Menu::new(vec![
+            MenuEntry::button(PauseAction::Quit, 12.0, 46.0, 40.0, 7.0, key::PAUSE_QUIT)
+                .with_key(KEY_Q),
+            MenuEntry::button(
+                PauseAction::Options,
+                12.0,
+                34.0,
+                40.0,
+                7.0,
+                key::MENU_OPTIONS,
+            )
+            .with_key(KEY_S),
+            MenuEntry::button(
+                PauseAction::Restart,
+                12.0,
+                22.0,
+                40.0,
+                7.0,
+                key::PAUSE_RESTART,
+            )
+            .with_key(KEY_R),
+            MenuEntry::button(
+                PauseAction::Resume,
+                12.0,
+                10.0,
+                40.0,
+                7.0,
+                key::PAUSE_RESUME,
+            )
+            .with_key(KEY_A),
+        ])
+        .with_focus(PauseAction::Resume);
+        menu.set_enabled(PauseAction::Restart, can_restart);
+        Self { menu }
+    }
+}
+
+impl Scene for PauseScene {
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(PauseAction::Resume)) | Some(MenuEvent::Back) => {
+                Transition::Pop(Signal::Back)
+            }
+            Some(MenuEvent::Activated(PauseAction::Restart)) => Transition::Pop(Signal::Restart),
+            Some(MenuEvent::Activated(PauseAction::Options)) => {
+                Transition::Push(Box::new(OptionsScene::new()))
+            }
+            Some(MenuEvent::Activated(PauseAction::Quit)) => {
+                Transition::Reset(Box::new(MenuScene::new()))
+            }
+            _ => Transition::None,
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        drawer.draw_rectangle(0, 0, 64, 64, Color { r: 0, g: 0, b: 0, a: 170 });
+        self.menu.draw(ctx, drawer);
+    }
+    fn is_overlay(&self) -> bool {
+        true
+    }
+    fn effect(&self) -> Option<Effect> {
+        None
+    }
+}
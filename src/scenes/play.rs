@@ -0,0 +1,231 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::drawable::Simulation;
+use crate::effect::{Effect, ScreenEffect};
+use crate::node::{AnimatedButton, GameMode, Gamestate, NumberDisplay};
+use crate::scene::{Context, Scene, Signal, Transition};
+use crate::scenes::{GameOverScene, PauseScene, RoundOverScene};
+use crate::score::{daily_seed, today};
+use rand::prelude::*;
+use rand_pcg::Pcg32;
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+const TIME_ATTACK_START: u64 = 30000;
+const CHEESE_BONUS: u64 = 2000;
+const EXIT_BONUS: u64 = 5000;
+
+pub struct PlayScene {
+    sim: Simulation,
+    mode: GameMode,
+    daily_date: String,
+    //true for the first daily run of the day, the only one whose score is kept
+    scored: bool,
+    total_points: NumberDisplay,
+    extra_cheese: AnimatedButton,
+    countdown: NumberDisplay,
+    deadline: u64,
+    //false until the first update, which only comes once the entry effect is over
+    started: bool,
+    versus_round: u32,
+    round_start: u64,
+    level_effect: Option<ScreenEffect>,
+}
+
+impl PlayScene {
+    pub fn new(mode: GameMode) -> Self {
+        let daily_date = today();
+        //every daily run of the same day plays the same sequence of levels
+        let rand_gen = match mode {
+            GameMode::Daily => Pcg32::seed_from_u64(daily_seed(&daily_date)),
+            _ => Pcg32::from_entropy(),
+        };
+        PlayScene::with_simulation(Simulation::new(mode, 0, rand_gen), mode, daily_date, 0)
+    }
+    //in versus the players swap between mouse and spider every round
+    pub fn versus(round: u32) -> Self {
+        let sim = Simulation::new(
+            GameMode::Versus,
+            (round % 2) as usize,
+            Pcg32::from_entropy(),
+        );
+        PlayScene::with_simulation(sim, GameMode::Versus, today(), round)
+    }
+    fn with_simulation(
+        sim: Simulation,
+        mode: GameMode,
+        daily_date: String,
+        versus_round: u32,
+    ) -> Self {
+        Self {
+            sim,
+            mode,
+            daily_date,
+            scored: false,
+            total_points: NumberDisplay::new(1.0, 62.0),
+            extra_cheese: AnimatedButton::new(0.0, 0.0, 64.0, 10.0),
+            countdown: NumberDisplay::new(54.0, 62.0),
+            deadline: TIME_ATTACK_START,
+            started: false,
+            versus_round,
+            round_start: 0,
+            level_effect: None,
+        }
+    }
+    fn mouse_player(&self) -> usize {
+        (self.versus_round % 2) as usize
+    }
+}
+
+impl Scene for PlayScene {
+    fn enter(&mut self, ctx: &mut Context) {
+        //the game clock is held while the wipe still covers the level, the countdown must not run behind it
+        ctx.clock.pause();
+        if (self.mode == GameMode::Daily) {
+            self.scored = ctx.daily_history.start(&self.daily_date);
+        }
+        ctx.music.play_layers(
+            ctx.resources.music("play"),
+            ctx.resources.music("play_tense"),
+        );
+        self.countdown.value = (TIME_ATTACK_START / 1000) as u32;
+    }
+    //quitting from the pause menu drops the level while the clock is still stopped
+    fn exit(&mut self, ctx: &mut Context) {
+        ctx.clock.resume();
+    }
+    fn resume(&mut self, ctx: &mut Context, signal: Signal) {
+        if (signal == Signal::Restart) {
+            self.sim.restart_level();
+        }
+        ctx.clock.resume();
+    }
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        if (!self.started) {
+            self.started = true;
+            ctx.clock.resume();
+            self.deadline = ctx.clock.now() + TIME_ATTACK_START;
+            self.round_start = ctx.clock.now();
+        }
+        //the game clock and the input are held while the next level is swapped in
+        if let Some(effect) = &mut self.level_effect {
+            if (effect.should_swap(&ctx.ui_clock)) {
+                self.sim.next_level();
+                let events = self.sim.take_events();
+                ctx.play_events(audio, &events, &self.sim.listeners());
+                self.total_points.value = self.sim.score();
+                if (!self.sim.has_spiders()) {
+                    self.extra_cheese.click(&ctx.clock);
+                }
+            }
+            if (self
+                .level_effect
+                .as_ref()
+                .map_or(true, |el| el.is_done(&ctx.ui_clock)))
+            {
+                self.level_effect = None;
+                ctx.clock.resume();
+            }
+            return Transition::None;
+        }
+        if (handle.is_key_released(KEY_ESCAPE) || !handle.is_window_focused()) {
+            ctx.clock.pause();
+            //a daily run cannot be restarted, the level it died on is part of the score
+            let can_restart = !matches!(self.mode, GameMode::Versus | GameMode::Daily);
+            return Transition::Push(Box::new(PauseScene::new(can_restart)));
+        }
+        let cheese = self.sim.cheese_collected();
+        let inputs = [ctx.controls[0].read(handle), ctx.controls[1].read(handle)];
+        let mut state = self.sim.step(inputs);
+        let picked = self.sim.cheese_collected() - cheese;
+        let events = self.sim.take_events();
+        ctx.play_events(audio, &events, &self.sim.listeners());
+        let frame_events = self.sim.take_frame_events();
+        ctx.play_frame_events(audio, &frame_events, &self.sim.listeners());
+        ctx.music.set_mood(self.sim.danger(), self.sim.is_cleared());
+        self.total_points.value = self.sim.score();
+        if (self.mode == GameMode::TimeAttack) {
+            self.deadline += picked as u64 * CHEESE_BONUS;
+            if (state == Gamestate::NextLevel) {
+                self.deadline += EXIT_BONUS;
+            }
+            let remaining = self.deadline.saturating_sub(ctx.clock.now());
+            self.countdown.value = ((remaining + 999) / 1000) as u32;
+            if (remaining == 0) {
+                state = Gamestate::GameOver;
+            }
+        }
+        if (state == Gamestate::NextLevel) {
+            ctx.clock.pause();
+            self.level_effect = Some(ScreenEffect::new(Effect::Dissolve, &ctx.ui_clock));
+        }
+        if (state != Gamestate::GameOver) {
+            return Transition::None;
+        }
+        if (self.mode == GameMode::Versus) {
+            ctx.versus_score.record(
+                self.mouse_player(),
+                ctx.clock.elapsed_since(self.round_start),
+                self.sim.cheese_collected(),
+            );
+            return Transition::Replace(Box::new(RoundOverScene::new(self.versus_round)));
+        }
+        let score = self.sim.score() * self.sim.level_count();
+        if (self.scored) {
+            ctx.daily_history.finish(&self.daily_date, score);
+        }
+        Transition::Replace(Box::new(GameOverScene::new(
+            self.mode,
+            self.daily_date.clone(),
+            score,
+        )))
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        self.sim.draw(drawer, &ctx.resources, &ctx.clock);
+        self.total_points.draw(drawer, &ctx.ui_clock);
+        if (self.mode == GameMode::TimeAttack) {
+            self.countdown.set_color(if (self.countdown.value <= 5) {
+                Color { r: 230, g: 41, b: 55, a: 255 }
+            } else {
+                Color { r: 255, g: 103, b: 0, a: 255 }
+            });
+            self.countdown
+                .translate(64.0 - self.countdown.width(), 62.0);
+            self.countdown.draw(drawer, &ctx.ui_clock);
+        }
+        if (!self.sim.has_spiders()) {
+            self.extra_cheese.draw(
+                drawer,
+                &ctx.resources[ctx.resources.texture("extra_cheese")].texture,
+                &ctx.clock,
+            )
+        }
+        if let Some(effect) = &self.level_effect {
+            effect.draw(drawer, &ctx.ui_clock);
+        }
+    }
+    fn effect(&self) -> Option<Effect> {
+        Some(Effect::Wipe)
+    }
+}
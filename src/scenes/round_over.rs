@@ -0,0 +1,123 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::audio::Audio;
+use crate::effect::Effect;
+use crate::font::{self, Align};
+use crate::lang::key;
+use crate::menu::{Menu, MenuEntry, MenuEvent};
+use crate::node::NumberDisplay;
+use crate::scene::{Context, Scene, Transition};
+use crate::scenes::{MenuScene, PlayScene};
+use raylib::prelude::*;
+use KeyboardKey::*;
+
+#[derive(Copy, Clone, PartialEq)]
+enum RoundAction {
+    NextRound,
+}
+
+pub struct RoundOverScene {
+    round: u32,
+    menu: Menu<RoundAction>,
+}
+
+impl RoundOverScene {
+    pub fn new(round: u32) -> Self {
+        Self {
+            round,
+            menu: Menu::new(vec![MenuEntry::button(
+                RoundAction::NextRound,
+                17.0,
+                5.0,
+                30.0,
+                7.0,
+                key::ROUND_NEXT,
+            )
+            .with_key(KEY_A)]),
+        }
+    }
+}
+
+impl Scene for RoundOverScene {
+    fn update(
+        &mut self,
+        ctx: &mut Context,
+        handle: &mut RaylibHandle,
+        audio: &mut dyn Audio,
+    ) -> Transition {
+        if (handle.is_key_released(KEY_Q)) {
+            ctx.play_sound(audio, "click");
+            return Transition::Reset(Box::new(MenuScene::new()));
+        }
+        match self.menu.update(ctx, handle, audio) {
+            Some(MenuEvent::Activated(RoundAction::NextRound)) => {
+                Transition::Replace(Box::new(PlayScene::versus(self.round + 1)))
+            }
+            Some(MenuEvent::Back) => Transition::Reset(Box::new(MenuScene::new())),
+            _ => Transition::None,
+        }
+    }
+    fn draw(&mut self, ctx: &Context, drawer: &mut RaylibTextureMode<RaylibDrawHandle>) {
+        //last round on top, then the totals of each player as the mouse
+        let colors = [
+            Color { r: 255, g: 255, b: 255, a: 255 },
+            Color { r: 255, g: 161, b: 0, a: 255 },
+        ];
+        if let Some(round) = ctx.versus_score.last() {
+            NumberDisplay::draw_digits(
+                drawer,
+                &(round.survived / 1000).to_string(),
+                Vector2 { x: 2.0, y: 62.0 },
+                colors[round.mouse_player],
+            );
+            NumberDisplay::draw_digits(
+                drawer,
+                &round.cheese.to_string(),
+                Vector2 { x: 40.0, y: 62.0 },
+                colors[round.mouse_player],
+            );
+        }
+        for player in 0..2 {
+            let (survived, cheese) = ctx.versus_score.totals(player);
+            let row_y = 42.0 - (player as f32) * 12.0;
+            font::draw_text(
+                drawer,
+                &format!("{}{}", ctx.strings.get(key::ROUND_PLAYER), player + 1),
+                Vector2 { x: 32.0, y: row_y - 1.0 },
+                Align::Center,
+                colors[player],
+            );
+            NumberDisplay::draw_digits(
+                drawer,
+                &(survived / 1000).to_string(),
+                Vector2 { x: 2.0, y: row_y },
+                colors[player],
+            );
+            NumberDisplay::draw_digits(
+                drawer,
+                &cheese.to_string(),
+                Vector2 { x: 40.0, y: row_y },
+                colors[player],
+            );
+        }
+        self.menu.draw(ctx, drawer);
+    }
+    fn effect(&self) -> Option<Effect> {
+        Some(Effect::Dissolve)
+    }
+}
@@ -0,0 +1,243 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{Local, NaiveDate};
+use std::fs;
+
+const TABLE_SIZE: usize = 5;
+
+//one line per mode: the mode key followed by its best scores, highest first
+pub struct HighScores {
+    path: String,
+    tables: Vec<(String, Vec<u32>)>,
+}
+
+impl HighScores {
+    pub fn load(path: &str) -> Self {
+        let mut tables = Vec::new();
+        if let Ok(content) = fs::read_to_string(path) {
+            for line in content.lines() {
+                let mut fields = line.split_whitespace();
+                if let Some(mode) = fields.next() {
+                    let mut scores: Vec<u32> = fields.filter_map(|el| el.parse().ok()).collect();
+                    scores.sort_unstable_by(|a, b| b.cmp(a));
+                    scores.truncate(TABLE_SIZE);
+                    tables.push((mode.to_string(), scores));
+                }
+            }
+        }
+        Self {
+            path: path.to_string(),
+            tables,
+        }
+    }
+    pub fn table(&self, mode: &str) -> &[u32] {
+        match self.tables.iter().find(|el| el.0 == mode) {
+            Some(table) => &table.1,
+            None => &[],
+        }
+    }
+    pub fn best(&self, mode: &str) -> u32 {
+        self.table(mode).first().copied().unwrap_or(0)
+    }
+    //returns true when the score made it into the table
+    pub fn submit(&mut self, mode: &str, score: u32) -> bool {
+        let index = match self.tables.iter().position(|el| el.0 == mode) {
+            Some(index) => index,
+            None => {
+                self.tables.push((mode.to_string(), Vec::new()));
+                self.tables.len() - 1
+            }
+        };
+        let scores = &mut self.tables[index].1;
+        let rank = scores.iter().position(|el| score > *el).unwrap_or(scores.len());
+        if (rank >= TABLE_SIZE) {
+            return false;
+        }
+        scores.insert(rank, score);
+        scores.truncate(TABLE_SIZE);
+        self.save();
+        return true;
+    }
+    fn save(&self) {
+        let mut content = String::new();
+        for (mode, scores) in &self.tables {
+            content.push_str(mode);
+            for score in scores {
+                content.push_str(&format!(" {}", score));
+            }
+            content.push('\n');
+        }
+        if let Err(error) = fs::write(&self.path, content) {
+            eprintln!("cannot save high scores to {}: {}", self.path, error);
+        }
+    }
+}
+
+//one line per day: the date followed by the score of the only scored attempt
+pub struct DailyHistory {
+    path: String,
+    entries: Vec<(String, u32)>,
+}
+
+impl DailyHistory {
+    pub fn load(path: &str) -> Self {
+        let mut entries = Vec::new();
+        if let Ok(content) = fs::read_to_string(path) {
+            //a line that is not a YYYY-MM-DD date and a score was not written by us, it is dropped
+            for line in content.lines() {
+                let mut fields = line.split_whitespace();
+                let date = fields
+                    .next()
+                    .and_then(|el| NaiveDate::parse_from_str(el, "%Y-%m-%d").ok());
+                if let (Some(date), Some(Ok(score))) =
+                    (date, fields.next().map(|el| el.parse::<u32>()))
+                {
+                    entries.push((date.format("%Y-%m-%d").to_string(), score));
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Self {
+            path: path.to_string(),
+            entries,
+        }
+    }
+    pub fn score_of(&self, date: &str) -> Option<u32> {
+        self.entries
+            .iter()
+            .find(|el| el.0 == date)
+            .map(|el| el.1)
+    }
+    pub fn has_played(&self, date: &str) -> bool {
+        self.score_of(date).is_some()
+    }
+    //only the first attempt of a day is scored, it is saved with 0 as soon as it starts
+    //so quitting or closing the game does not earn another try, returns false for the others
+    pub fn start(&mut self, date: &str) -> bool {
+        if (self.has_played(date)) {
+            return false;
+        }
+        self.entries.push((date.to_string(), 0));
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        self.save();
+        return true;
+    }
+    //the final score of the attempt start() returned true for
+    pub fn finish(&mut self, date: &str, score: u32) {
+        if let Some(entry) = self.entries.iter_mut().find(|el| el.0 == date) {
+            entry.1 = score;
+            self.save();
+        }
+    }
+    //most recent days first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &(String, u32)> {
+        self.entries.iter().rev().take(count)
+    }
+    fn save(&self) {
+        let mut content = String::new();
+        for (date, score) in &self.entries {
+            content.push_str(&format!("{} {}\n", date, score));
+        }
+        if let Err(error) = fs::write(&self.path, content) {
+            eprintln!("cannot save daily history to {}: {}", self.path, error);
+        }
+    }
+}
+
+pub fn today() -> String {
+    Local::now().date_naive().format("%Y-%m-%d").to_string()
+}
+
+pub fn daily_seed(date: &str) -> u64 {
+    date.chars()
+        .filter_map(|el| el.to_digit(10))
+        .fold(0, |seed, digit| seed * 10 + digit as u64)
+}
+
+pub struct RoundResult {
+    pub mouse_player: usize,
+    pub survived: u64,
+    pub cheese: u32,
+}
+
+//results of a versus match, only kept for the current session
+pub struct VersusScore {
+    rounds: Vec<RoundResult>,
+}
+
+impl VersusScore {
+    pub fn new() -> Self {
+        Self { rounds: Vec::new() }
+    }
+    pub fn record(&mut self, mouse_player: usize, survived: u64, cheese: u32) {
+        self.rounds.push(RoundResult {
+            mouse_player,
+            survived,
+            cheese,
+        });
+    }
+    pub fn last(&self) -> Option<&RoundResult> {
+        self.rounds.last()
+    }
+    //total survival time and cheese of a player over the rounds played as the mouse
+    pub fn totals(&self, player: usize) -> (u64, u32) {
+        self.rounds
+            .iter()
+            .filter(|el| el.mouse_player == player)
+            .fold((0, 0), |acc, el| (acc.0 + el.survived, acc.1 + el.cheese))
+    }
+    pub fn round_count(&self) -> usize {
+        self.rounds.len()
+    }
+    pub fn clear(&mut self) {
+        self.rounds.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn daily_history_drops_bad_lines() {
+        let path = env::temp_dir().join("cheese_adventure_daily_test.txt");
+        let path = path.to_string_lossy().into_owned();
+        fs::write(
+            &path,
+            "2024-03-05 120\n5 10\n2024-13-01 7\n2024-03-06 40\nyesterday 3\n2024-03-07\n",
+        )
+        .unwrap();
+        let mut history = DailyHistory::load(&path);
+        let recent: Vec<_> = history.recent(10).cloned().collect();
+        assert_eq!(
+            recent,
+            vec![
+                ("2024-03-06".to_string(), 40),
+                ("2024-03-05".to_string(), 120)
+            ]
+        );
+        //the first attempt of a day is saved at once and only it can be finished
+        assert!(history.start("2024-03-08"));
+        assert!(!history.start("2024-03-08"));
+        assert_eq!(DailyHistory::load(&path).score_of("2024-03-08"), Some(0));
+        history.finish("2024-03-08", 90);
+        assert_eq!(DailyHistory::load(&path).score_of("2024-03-08"), Some(90));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::assets::{Handle, Sounds};
+use crate::audio::Audio;
+use crate::clock::GameClock;
+use crate::node::GameEvent;
+use rand::prelude::*;
+use raylib::prelude::{Sound, Vector2};
+
+//a sound this far to the side of the mouse is all in one ear
+const PAN_WIDTH: f32 = 32.0;
+//a sound this far from the mouse plays at MIN_GAIN, closer ones get louder
+const HEARING_DISTANCE: f32 = 64.0;
+const MIN_GAIN: f32 = 0.25;
+
+//the sound each gameplay event makes
+pub fn sound_for(event: &GameEvent) -> &'static str {
+    match event {
+        GameEvent::LevelStarted => "level_start",
+        GameEvent::CheesePicked(_) => "pickup",
+        GameEvent::ExitOpened(_) => "exit_open",
+        GameEvent::PlayerCaught(_) => "death",
+        GameEvent::SpiderWoke(_) => "spider_wake",
+        GameEvent::SpiderStep(_) => "spider_step",
+        GameEvent::PowerUp => "power_up",
+    }
+}
+
+//where on the field an event happened, None for the ones heard everywhere
+pub fn source(event: &GameEvent) -> Option<Vector2> {
+    match event {
+        GameEvent::CheesePicked(pos)
+        | GameEvent::ExitOpened(pos)
+        | GameEvent::PlayerCaught(pos)
+        | GameEvent::SpiderWoke(pos)
+        | GameEvent::SpiderStep(pos) => Some(pos.clone()),
+        GameEvent::LevelStarted | GameEvent::PowerUp => None,
+    }
+}
+
+//pan and gain of a sound at source as heard by the closest listener,
+//in the middle and at full volume when there is nobody to hear it
+pub fn placement(source: &Vector2, listeners: &[Vector2]) -> (f32, f32) {
+    let distance =
+        |el: &Vector2| ((el.x - source.x).powf(2.0) + (el.y - source.y).powf(2.0)).sqrt();
+    match listeners
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    {
+        Some(listener) => (
+            ((source.x - listener.x) / PAN_WIDTH).clamp(-1.0, 1.0),
+            (1.0 - distance(listener) / HEARING_DISTANCE).max(MIN_GAIN),
+        ),
+        None => (0.0, 1.0),
+    }
+}
+
+//plays sound effects with a bit of randomness and keeps each one under its instance cap
+pub struct Sfx {
+    rng: StdRng,
+    playing: Vec<(Handle<Sound>, u64)>,
+}
+
+impl Sfx {
+    pub fn new() -> Self {
+        Self { rng: StdRng::from_entropy(), playing: Vec::new() }
+    }
+    pub fn play(&mut self, sounds: &Sounds, audio: &mut dyn Audio, clock: &GameClock, id: &str) {
+        self.play_placed(sounds, audio, clock, id, (0.0, 1.0));
+    }
+    //listeners are the mice, events with a position are panned and faded relative to the closest one
+    pub fn play_events(
+        &mut self,
+        sounds: &Sounds,
+        audio: &mut dyn Audio,
+        clock: &GameClock,
+        events: &[GameEvent],
+        listeners: &[Vector2],
+    ) {
+        for event in events {
+            let placement = source(event).map_or((0.0, 1.0), |el| placement(&el, listeners));
+            self.play_placed(sounds, audio, clock, sound_for(event), placement);
+        }
+    }
+    //sounds asked for by animation frames, each with the position of the sprite that played it
+    pub fn play_frame_events(
+        &mut self,
+        sounds: &Sounds,
+        audio: &mut dyn Audio,
+        clock: &GameClock,
+        events: &[(String, Vector2)],
+        listeners: &[Vector2],
+    ) {
+        for (id, pos) in events {
+            self.play_placed(sounds, audio, clock, id, placement(pos, listeners));
+        }
+    }
+    //placement is the pan and gain from placement()
+    pub fn play_placed(
+        &mut self,
+        sounds: &Sounds,
+        audio: &mut dyn Audio,
+        clock: &GameClock,
+        id: &str,
+        (pan, gain): (f32, f32),
+    ) {
+        let sound = sounds.handle(id);
+        let settings = sounds.settings(sound);
+        let now = clock.now();
+        self.playing.retain(|el| el.1 > now);
+        let instances = self.playing.iter().filter(|el| el.0 == sound).count() as u32;
+        if (instances >= settings.max) {
+            return;
+        }
+        self.playing.push((sound, now + sounds.length(sound)));
+        let volume = settings.volume + self.rng.gen_range(-1.0..=1.0) * settings.volume_vary;
+        let pitch = settings.pitch + self.rng.gen_range(-1.0..=1.0) * settings.pitch_vary;
+        audio.play_sound(
+            sound,
+            (volume * gain * sounds.volume()).clamp(0.0, 1.0),
+            pitch.max(0.1),
+            pan,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::SoundSettings;
+    use crate::audio::{AudioEvent, NullAudio, Recording};
+
+    #[test]
+    fn sounds_stay_under_their_cap() {
+        let mut settings = SoundSettings::new();
+        settings.max = 2;
+        let mut sounds = Sounds::new();
+        let pickup = sounds.insert("pickup", settings, 100);
+        let mut clock = GameClock::manual();
+        let mut sfx = Sfx::new();
+        let mut audio = Recording::new(NullAudio::new());
+        for _ in 0..3 {
+            sfx.play(&sounds, &mut audio, &clock, "pickup");
+        }
+        assert_eq!(
+            audio.events(),
+            &[AudioEvent::Sound(pickup), AudioEvent::Sound(pickup)]
+        );
+        clock.advance(100);
+        sfx.play(&sounds, &mut audio, &clock, "pickup");
+        assert_eq!(audio.events().len(), 3);
+    }
+
+    #[test]
+    fn sounds_are_placed_by_the_closest_listener() {
+        let source = Vector2 { x: 40.0, y: 10.0 };
+        let far = Vector2 { x: 0.0, y: 60.0 };
+        let near = Vector2 { x: 24.0, y: 10.0 };
+        assert_eq!(placement(&source, &[far, near]), (0.5, 0.75));
+        assert_eq!(placement(&source, &[]), (0.0, 1.0));
+        assert_eq!(placement(&source, &[far]).1, MIN_GAIN);
+    }
+}
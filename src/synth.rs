@@ -0,0 +1,286 @@
+/*
+Cheese Adventure a small and simple game
+    Copyright (C) 2022  ninomerlino
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::embedded;
+use std::collections::HashMap;
+use std::fs;
+
+pub const PRESETS: &str = "presets.txt";
+pub const SAMPLE_RATE: u32 = 22050;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Waveform {
+    Square,
+    Saw,
+    Noise,
+}
+
+//everything needed to generate one sound effect, times are in seconds
+#[derive(Copy, Clone)]
+pub struct Preset {
+    pub wave: Waveform,
+    pub frequency: f32,
+    //hertz added every second, negative values slide down
+    pub slide: f32,
+    //share of the period the square wave stays high
+    pub duty: f32,
+    pub attack: f32,
+    pub sustain: f32,
+    pub decay: f32,
+    pub volume: f32,
+    //bit crush, 0 keeps the full resolution
+    pub bits: u32,
+    //sample rate the sound is held at, 0 keeps the full rate
+    pub rate: f32,
+}
+
+impl Preset {
+    fn new() -> Self {
+        Self {
+            wave: Waveform::Square,
+            frequency: 440.0,
+            slide: 0.0,
+            duty: 0.5,
+            attack: 0.0,
+            sustain: 0.1,
+            decay: 0.2,
+            volume: 0.8,
+            bits: 0,
+            rate: 0.0,
+        }
+    }
+    fn envelope(&self, time: f32) -> f32 {
+        if (time < self.attack) {
+            time / self.attack
+        } else if (time < self.attack + self.sustain) {
+            1.0
+        } else if (self.decay > 0.0) {
+            (1.0 - (time - self.attack - self.sustain) / self.decay).max(0.0)
+        } else {
+            0.0
+        }
+    }
+    pub fn render(&self) -> Vec<i16> {
+        let length = ((self.attack + self.sustain + self.decay) * SAMPLE_RATE as f32) as usize;
+        let hold = if (self.rate > 0.0) {
+            ((SAMPLE_RATE as f32 / self.rate) as usize).max(1)
+        } else {
+            1
+        };
+        //xorshift, the same preset always renders to the same samples
+        let mut seed: u32 = 0x2545_f491;
+        let mut noise = 0.0;
+        let mut phase = 0.0;
+        let mut held = 0.0;
+        let mut samples = Vec::with_capacity(length);
+        for index in 0..length {
+            let time = index as f32 / SAMPLE_RATE as f32;
+            let frequency = (self.frequency + self.slide * time).max(20.0);
+            phase += frequency / SAMPLE_RATE as f32;
+            if (phase >= 1.0) {
+                phase -= 1.0;
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                noise = (seed as f32 / u32::MAX as f32) * 2.0 - 1.0;
+            }
+            let wave = match self.wave {
+                Waveform::Square => {
+                    if (phase < self.duty) {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::Saw => phase * 2.0 - 1.0,
+                Waveform::Noise => noise,
+            };
+            let mut sample = wave * self.envelope(time) * self.volume;
+            if (self.bits > 0) {
+                let levels = (1u32 << (self.bits.min(16) - 1)) as f32;
+                sample = (sample * levels).round() / levels;
+            }
+            if (index % hold == 0) {
+                held = sample;
+            }
+            samples.push((held.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+        }
+        return samples;
+    }
+}
+
+//"name option=value..." per line, # starts a comment, missing options keep their default
+pub fn parse_presets(text: &str) -> (HashMap<String, Preset>, Vec<(usize, String)>) {
+    let mut presets = HashMap::new();
+    let mut errors = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        match parse_options(words) {
+            Ok(preset) => {
+                presets.insert(name.to_string(), preset);
+            }
+            Err(reason) => errors.push((number + 1, reason)),
+        }
+    }
+    (presets, errors)
+}
+
+fn parse_options<'a>(options: impl Iterator<Item = &'a str>) -> Result<Preset, String> {
+    let mut preset = Preset::new();
+    for option in options {
+        let (name, value) = option
+            .split_once('=')
+            .ok_or("preset options are name=value")?;
+        if (name == "wave") {
+            preset.wave = match value {
+                "square" => Waveform::Square,
+                "saw" => Waveform::Saw,
+                "noise" => Waveform::Noise,
+                _ => return Err(format!("unknown wave {}", value)),
+            };
+            continue;
+        }
+        let value = value
+            .parse::<f32>()
+            .map_err(|_| format!("{} is not a number", value))?;
+        match name {
+            "frequency" => preset.frequency = value,
+            "slide" => preset.slide = value,
+            "duty" => preset.duty = value.clamp(0.0, 1.0),
+            "attack" => preset.attack = value.max(0.0),
+            "sustain" => preset.sustain = value.max(0.0),
+            "decay" => preset.decay = value.max(0.0),
+            "volume" => preset.volume = value.clamp(0.0, 1.0),
+            "bits" => preset.bits = value.clamp(0.0, 16.0) as u32,
+            "rate" => preset.rate = value.max(0.0),
+            _ => return Err(format!("unknown preset option {}", name)),
+        }
+    }
+    Ok(preset)
+}
+
+//a 16 bit wav file with the channels interleaved, what raylib loads from memory
+//and what the --render-sfx option writes
+pub fn to_wav(samples: &[i16], sample_rate: u32, channels: u16) -> Vec<u8> {
+    let data_size = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    wav.extend_from_slice(&(channels * 2).to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    return wav;
+}
+
+//what --render-sfx runs, so a preset can be listened to without starting the game
+pub fn render_to_file(name: &str, out: &str) -> Result<(), String> {
+    let text = embedded::read_to_string(PRESETS).map_err(|el| format!("{}: {}", PRESETS, el))?;
+    let (presets, errors) = parse_presets(&text);
+    for (number, reason) in errors {
+        eprintln!("{}:{}: {}", PRESETS, number, reason);
+    }
+    let preset = presets
+        .get(name)
+        .ok_or(format!("there is no preset {} in {}", name, PRESETS))?;
+    fs::write(out, to_wav(&preset.render(), SAMPLE_RATE, 1))
+        .map_err(|el| format!("{}: {}", out, el))?;
+    println!("wrote {}", out);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_always_render_the_same_samples() {
+        let (presets, errors) = parse_presets(&embedded::read_to_string(PRESETS).unwrap());
+        assert!(errors.is_empty());
+        for preset in presets.values() {
+            let samples = preset.render();
+            assert!(!samples.is_empty());
+            assert!(samples == preset.render());
+        }
+        let (presets, _) = parse_presets("hiss wave=noise sustain=0.5 decay=0.5 bits=4 rate=8000");
+        let samples = presets["hiss"].render();
+        assert_eq!(samples.len(), SAMPLE_RATE as usize);
+        assert!(samples == presets["hiss"].render());
+    }
+
+    #[test]
+    fn wavs_have_a_header_for_their_samples() {
+        let wav = to_wav(&[1, -2, 3, -4], 22050, 2);
+        let word = |at: usize| u16::from_le_bytes([wav[at], wav[at + 1]]);
+        let long = |at: usize| u32::from_le_bytes([wav[at], wav[at + 1], wav[at + 2], wav[at + 3]]);
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(long(4), 36 + 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(long(16), 16);
+        //pcm, channels, sample rate, bytes per second, bytes per frame, bits per sample
+        assert_eq!(word(20), 1);
+        assert_eq!(word(22), 2);
+        assert_eq!(long(24), 22050);
+        assert_eq!(long(28), 22050 * 4);
+        assert_eq!(word(32), 4);
+        assert_eq!(word(34), 16);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(long(40), 8);
+        //the second sample, after the 44 bytes of header and the first one
+        assert_eq!(word(46) as i16, -2);
+    }
+
+    #[test]
+    fn preset_errors_name_their_line() {
+        let text = "# a comment\n\
+                    \n\
+                    good frequency=880 wave=saw\n\
+                    no_equals frequency\n\
+                    bad_wave wave=sine\n\
+                    bad_number decay=long\n\
+                    bad_option colour=3\n\
+                    clamped duty=4 bits=99\n";
+        let (presets, errors) = parse_presets(text);
+        assert_eq!(
+            errors,
+            vec![
+                (4, "preset options are name=value".to_string()),
+                (5, "unknown wave sine".to_string()),
+                (6, "long is not a number".to_string()),
+                (7, "unknown preset option colour".to_string()),
+            ]
+        );
+        assert_eq!(presets.len(), 2);
+        assert!(presets["good"].frequency == 880.0 && presets["good"].wave == Waveform::Saw);
+        assert!(presets["clamped"].duty == 1.0 && presets["clamped"].bits == 16);
+    }
+}